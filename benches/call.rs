@@ -0,0 +1,91 @@
+//! Per-invocation overhead of creating a call by name versus reusing one call with `reset`.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use iree_rs::types::{
+    allocator::IreeAllocator,
+    bytespan::IreeConstByteSpan,
+    hal_buffer::{IreeHalBufferView, IreeHalBufferViewParamsBuilder},
+    runtime::{
+        call::IreeRuntimeCall,
+        instance::{IreeRuntimeInstance, IreeRuntimeInstanceOptionsBuilder},
+        session::{IreeRuntimeSession, IreeRuntimeSessionOptionsBuilder},
+    },
+};
+use iree_sys::iree::runtime::api::{
+    iree_hal_buffer_usage_bits_t_IREE_HAL_BUFFER_USAGE_DEFAULT,
+    iree_hal_element_types_t_IREE_HAL_ELEMENT_TYPE_FLOAT_32,
+    iree_hal_encoding_types_t_IREE_HAL_ENCODING_TYPE_DENSE_ROW_MAJOR,
+    iree_hal_memory_type_bits_t_IREE_HAL_MEMORY_TYPE_DEVICE_LOCAL, iree_runtime_call_flags_t,
+};
+
+static SIMPLE_MUL_VMFB: &[u8] = include_bytes!("../iree-sys/examples/simple_mul_module.vmfb");
+
+fn bench_call(c: &mut Criterion) {
+    let allocator = IreeAllocator::system_allocator();
+    let instance = IreeRuntimeInstance::try_from_options(
+        &IreeRuntimeInstanceOptionsBuilder::default()
+            .use_all_available_drivers()
+            .build(),
+        &allocator,
+    )
+    .unwrap();
+    let device = instance.try_create_default_device("local-sync").unwrap();
+    let session = IreeRuntimeSession::create_with_device(
+        &instance,
+        &IreeRuntimeSessionOptionsBuilder::default().build(),
+        &device,
+        &allocator,
+    )
+    .unwrap();
+    session
+        .append_bytecode_module_from_memory(SIMPLE_MUL_VMFB, &IreeAllocator::null())
+        .unwrap();
+
+    let data = [1.0f32, 2.0, 3.0, 4.0];
+    let input = IreeHalBufferView::allocate_buffer_copy(
+        &device,
+        &session.device_allocator(),
+        &vec![data.len()],
+        iree_hal_element_types_t_IREE_HAL_ELEMENT_TYPE_FLOAT_32,
+        iree_hal_encoding_types_t_IREE_HAL_ENCODING_TYPE_DENSE_ROW_MAJOR,
+        &IreeHalBufferViewParamsBuilder::default()
+            .type_(iree_hal_memory_type_bits_t_IREE_HAL_MEMORY_TYPE_DEVICE_LOCAL.0)
+            .usage(iree_hal_buffer_usage_bits_t_IREE_HAL_BUFFER_USAGE_DEFAULT.0)
+            .build(),
+        &IreeConstByteSpan::from_slice(&data),
+    )
+    .unwrap();
+
+    let run = |call: &mut IreeRuntimeCall| {
+        call.inputs_push_back_buffer_view(&input).unwrap();
+        call.inputs_push_back_buffer_view(&input).unwrap();
+        call.invoke(iree_runtime_call_flags_t::default()).unwrap();
+        call.outputs_pop_front_buffer_view().unwrap()
+    };
+
+    c.bench_function("call by name per invocation", |b| {
+        b.iter(|| {
+            let mut call = session.get_call_by_name("module.simple_mul").unwrap();
+            run(&mut call)
+        })
+    });
+
+    let function = session.lookup_function("module.simple_mul").unwrap();
+    c.bench_function("call per invocation from a looked up function", |b| {
+        b.iter(|| {
+            let mut call = IreeRuntimeCall::initialize(&session, &function).unwrap();
+            run(&mut call)
+        })
+    });
+
+    let mut call = IreeRuntimeCall::initialize(&session, &function).unwrap();
+    c.bench_function("reused call", |b| {
+        b.iter(|| {
+            call.reset();
+            run(&mut call)
+        })
+    });
+}
+
+criterion_group!(benches, bench_call);
+criterion_main!(benches);
@@ -52,7 +52,7 @@ pub fn run_resnet18() -> Result<(), IreeError> {
     )?;
 
     // load resnet18 vmfb to session
-    session.append_bytecode_module_from_memory(RESNET18_VMFB.as_slice(), &allocator)?;
+    session.append_bytecode_module_from_memory(RESNET18_VMFB.as_slice(), &IreeAllocator::null())?;
 
     // // get the entry function
     let mut call = session.get_call_by_name("module.forward")?;
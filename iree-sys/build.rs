@@ -2,167 +2,875 @@ extern crate bindgen;
 
 use std::env;
 use std::path::{Path, PathBuf};
-use std::process::{Command, Output};
+#[cfg(feature = "vendored")]
+use std::process::Command;
 
-use git2::Repository;
+/// IREE release the bindings are generated and tested against. It has to ship everything bound
+/// here: the io parameters module, executable plugins and `iree.abi.declaration` reflection.
+static IREE_REVISION: &str = "iree-3.1.0";
 
-static IREE_SAMPLES_REPO: &str = "https://github.com/iree-org/iree-samples";
+/// VM bytecode version (major, minor) of `IREE_REVISION`, exported as
+/// `iree_sys::IREE_VM_BYTECODE_VERSION`. Checked against the runtime's headers when they include it.
+static IREE_VM_BYTECODE_VERSION: (u16, u16) = (15, 0);
+
+#[cfg(feature = "vendored")]
 static IREE_REPO: &str = "https://github.com/iree-org/iree";
 
-fn shallow_clone(path: &Path, repo: &str) -> Repository {
-    let mut child = Command::new("git")
-        .args(&[
+/// Runs git and returns its trimmed stdout, or `None` if it failed.
+#[cfg(feature = "vendored")]
+fn git(dir: Option<&Path>, args: &[&str]) -> Option<String> {
+    let mut command = Command::new("git");
+    if let Some(dir) = dir {
+        command.arg("-C").arg(dir);
+    }
+    let output = command.args(args).output().expect("failed to execute git");
+    if !output.status.success() {
+        eprintln!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr)
+        );
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(feature = "vendored")]
+fn clone_iree(path: &Path) {
+    git(
+        None,
+        &[
             "clone",
             "--depth",
             "1",
+            "--branch",
+            IREE_REVISION,
             "--recurse-submodules",
             "--shallow-submodules",
             "-j10",
-            repo,
+            IREE_REPO,
             path.to_str().unwrap(),
-        ])
-        .spawn()
-        .expect("failed to execute process");
-    child.wait().unwrap();
+        ],
+    )
+    .unwrap_or_else(|| panic!("failed to clone {} at {}", IREE_REPO, IREE_REVISION));
+}
 
-    git2::Repository::open(path).unwrap()
+/// Fails the build if a git checkout of IREE is not at `IREE_REVISION`. Returns the revision, or
+/// `None` if the source is not a checkout.
+#[cfg(feature = "vendored")]
+fn check_source_revision(iree_dir: &Path) -> Option<String> {
+    if !iree_dir.join(".git").exists() {
+        // not a checkout (e.g. an unpacked release), so the revision has to be given
+        return None;
+    }
+    let head = git(Some(iree_dir), &["rev-parse", "HEAD"]);
+    let expected = git(
+        Some(iree_dir),
+        &["rev-parse", &format!("{}^{{commit}}", IREE_REVISION)],
+    );
+    match (head, expected) {
+        (Some(head), Some(expected)) if head == expected => Some(IREE_REVISION.to_string()),
+        (head, expected) => panic!(
+            "IREE source at {} is at commit {}, but iree-sys expects {} ({}). Check out that \
+             revision, or set IREE_SYS_SKIP_VERSION_CHECK=1 to build against it anyway.",
+            iree_dir.display(),
+            head.unwrap_or_else(|| "<unknown>".to_string()),
+            IREE_REVISION,
+            expected.unwrap_or_else(|| "not present in the checkout".to_string()),
+        ),
+    }
 }
 
-/// use cached repo if it exists, otherwise clone it
-fn get_repo(path: &Path, repo: &str) -> git2::Repository {
-    println!("Checking for cached repo at: {}", path.to_str().unwrap());
-    if path.exists() {
-        git2::Repository::open(path).unwrap()
-    } else {
-        // shallow clone
-        shallow_clone(path, repo)
-    }
-}
-
-/// Clones the IREE repository and builds it.
-fn clone_and_build_iree(out_dir: &Path) -> PathBuf {
-    // clone IREE repo
-    let iree_dir = out_dir.join("iree");
-    let iree = get_repo(iree_dir.as_path(), IREE_REPO);
-
-    // clone IREE samples repo
-    let iree_samples = get_repo(&out_dir.join("iree-samples"), IREE_SAMPLES_REPO);
-
-    // make build directory
-    let mut iree_samples_build_path = out_dir.join("iree-samples-build");
-    if iree_samples_build_path.exists() {
-        // already built!
-        return iree_samples_build_path;
-    }
-    std::fs::create_dir_all(iree_samples_build_path.clone()).unwrap();
-
-    // build iree-samples
-    cmake::Config::new(out_dir.join("iree-samples/runtime-library"))
-        .define("BUILD_SHARED_LIBS", "OFF")
-        .define("CMAKE_C_COMPILER", "clang")
-        .define("CMAKE_CXX_COMPILER", "clang++")
-        .define(
-            "IREE_ROOT_DIR",
-            out_dir
-                .join("iree")
-                .canonicalize()
-                .unwrap()
-                .to_str()
-                .unwrap(),
-        )
-        .out_dir(iree_samples_build_path.clone())
-        .build();
+/// Fails the build if the runtime was built from a revision other than `IREE_REVISION`, and warns if
+/// that can't be told. The revision comes from the source checkout or pkg-config version if known,
+/// otherwise from `IREE_RUNTIME_REVISION`. Release versions (`3.1.0`) and tags (`iree-3.1.0`,
+/// `candidate-20230201.416`) are both accepted.
+fn check_runtime_revision(iree_runtime: &IreeRuntime) {
+    let revision = iree_runtime
+        .revision
+        .clone()
+        .or_else(|| env::var("IREE_RUNTIME_REVISION").ok());
+    let version = |revision: &str| {
+        let revision = revision.trim();
+        ["candidate-", "iree-", "v"]
+            .iter()
+            .fold(revision, |revision, prefix| {
+                revision.strip_prefix(prefix).unwrap_or(revision)
+            })
+            .to_string()
+    };
+    match revision {
+        Some(revision) if version(&revision) == version(IREE_REVISION) => {}
+        Some(revision) => panic!(
+            "The IREE runtime with headers at {} was built from revision {}, but iree-sys was \
+             generated against {}. Use a matching IREE build, or set \
+             IREE_SYS_SKIP_VERSION_CHECK=1 to build against it anyway.",
+            iree_runtime.include_dir.display(),
+            revision,
+            IREE_REVISION,
+        ),
+        None => println!(
+            "cargo:warning=Could not tell which IREE revision the runtime with headers at {} was \
+             built from; iree-sys expects {}. Set IREE_RUNTIME_REVISION to check it.",
+            iree_runtime.include_dir.display(),
+            IREE_REVISION,
+        ),
+    }
+}
+
+fn skip_version_check() -> bool {
+    env::var("IREE_SYS_SKIP_VERSION_CHECK").map_or(false, |v| v == "1")
+}
 
-    // add library path to linker
+/// Reads the VM bytecode version from the runtime's headers. Only source trees and vendored builds
+/// install the header defining it, so this is `None` for most prebuilt runtimes.
+fn header_bytecode_version(include_dir: &Path) -> Option<(u16, u16)> {
+    let header = [
+        "iree/vm/bytecode/module_impl.h",
+        "iree/vm/bytecode_module_impl.h",
+    ]
+    .iter()
+    .map(|header| include_dir.join(header))
+    .find(|header| header.exists())?;
+    let source = std::fs::read_to_string(header).ok()?;
+    let define = |name: &str| {
+        source.lines().find_map(|line| {
+            let mut tokens = line.split_whitespace();
+            match (tokens.next(), tokens.next(), tokens.next()) {
+                (Some("#define"), Some(define), Some(value)) if define == name => {
+                    value.parse().ok()
+                }
+                _ => None,
+            }
+        })
+    };
+    Some((
+        define("IREE_VM_BYTECODE_VERSION_MAJOR")?,
+        define("IREE_VM_BYTECODE_VERSION_MINOR")?,
+    ))
+}
 
-    iree_samples_build_path
+/// The runtime's VM bytecode version: read from its headers if possible, otherwise the one of
+/// `IREE_REVISION`. Fails the build if the headers disagree with the pinned version.
+fn runtime_bytecode_version(iree_runtime: &IreeRuntime) -> (u16, u16) {
+    match header_bytecode_version(&iree_runtime.include_dir) {
+        Some(version) if version == IREE_VM_BYTECODE_VERSION || skip_version_check() => version,
+        Some(version) => panic!(
+            "The IREE runtime with headers at {} uses VM bytecode version {}.{}, but iree-sys \
+             expects {}.{} for {}. Update IREE_VM_BYTECODE_VERSION in iree-sys/build.rs along with \
+             IREE_REVISION, or set IREE_SYS_SKIP_VERSION_CHECK=1 to build against it anyway.",
+            iree_runtime.include_dir.display(),
+            version.0,
+            version.1,
+            IREE_VM_BYTECODE_VERSION.0,
+            IREE_VM_BYTECODE_VERSION.1,
+            IREE_REVISION,
+        ),
+        None => IREE_VM_BYTECODE_VERSION,
+    }
 }
 
-fn main() {
-    let out_path = PathBuf::from(env::var("OUT_DIR").unwrap());
-    let iree_build_dir = clone_and_build_iree(out_path.as_path());
-    println!(
-        "cargo:rustc-link-search={}",
-        iree_build_dir.join("build/lib").to_str().unwrap()
-    );
+/// HAL drivers and executable loaders selectable through cargo features, with the CMake options
+/// enabling each of them. Loader features also enable the matching executable plugin support.
+#[cfg(feature = "vendored")]
+static HAL_FEATURES: &[(&str, &[&str])] = &[
+    ("driver-local-sync", &["IREE_HAL_DRIVER_LOCAL_SYNC"]),
+    ("driver-local-task", &["IREE_HAL_DRIVER_LOCAL_TASK"]),
+    (
+        "loader-embedded-elf",
+        &[
+            "IREE_HAL_EXECUTABLE_LOADER_EMBEDDED_ELF",
+            "IREE_HAL_EXECUTABLE_PLUGIN_EMBEDDED_ELF",
+        ],
+    ),
+    (
+        "loader-system-library",
+        &[
+            "IREE_HAL_EXECUTABLE_LOADER_SYSTEM_LIBRARY",
+            "IREE_HAL_EXECUTABLE_PLUGIN_SYSTEM_LIBRARY",
+        ],
+    ),
+];
 
-    // add built third party libraries to linker
-    // cpuinfo
-    println!(
-        "cargo:rustc-link-search={}",
-        iree_build_dir
-            .join("build/iree_core/third_party/cpuinfo/")
-            .to_str()
-            .unwrap()
-    );
+fn feature_enabled(feature: &str) -> bool {
+    env::var(format!(
+        "CARGO_FEATURE_{}",
+        feature.to_uppercase().replace('-', "_")
+    ))
+    .is_ok()
+}
 
-    // flatcc
-    println!(
-        "cargo:rustc-link-search={}",
-        iree_build_dir
-            .join("build/iree_core/build_tools/third_party/flatcc/")
-            .to_str()
-            .unwrap()
+/// Directory holding cloned sources and CMake builds: `IREE_SYS_CACHE_DIR` if set, so they survive
+/// `cargo clean` and are shared between target directories, otherwise `OUT_DIR`.
+#[cfg(feature = "vendored")]
+fn cache_dir(out_dir: &Path) -> PathBuf {
+    match env::var("IREE_SYS_CACHE_DIR") {
+        Ok(dir) => PathBuf::from(dir),
+        Err(_) => out_dir.to_path_buf(),
+    }
+}
+
+/// Runs `create` for `dir` unless an earlier run completed it. An exclusive lock on `<dir>.lock` is
+/// held meanwhile, so concurrent builds sharing the cache wait for each other instead of racing.
+#[cfg(feature = "vendored")]
+fn with_cache_entry(dir: &Path, create: impl FnOnce(&Path)) {
+    use fs4::FileExt;
+
+    std::fs::create_dir_all(dir.parent().unwrap()).expect("Unable to create directory");
+    let lock_path = PathBuf::from(format!("{}.lock", dir.display()));
+    let lock = std::fs::File::create(&lock_path)
+        .unwrap_or_else(|e| panic!("Unable to create {}: {}", lock_path.display(), e));
+    lock.lock_exclusive()
+        .unwrap_or_else(|e| panic!("Unable to lock {}: {}", lock_path.display(), e));
+
+    let complete = dir.join(".iree-sys-complete");
+    if !complete.exists() {
+        create(dir);
+        std::fs::write(&complete, "").expect("Unable to mark cache entry as complete");
+    }
+    // the lock is released when `lock` is dropped
+}
+
+/// Returns a source checkout, in order of precedence: the directory in `env_var`, a crate-local
+/// copy under `vendor/`, or a pinned clone in the cache directory.
+#[cfg(feature = "vendored")]
+fn source_dir(env_var: &str, name: &str, cache_dir: &Path, clone: fn(&Path)) -> PathBuf {
+    if let Ok(dir) = env::var(env_var) {
+        return PathBuf::from(dir);
+    }
+    let vendored = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap())
+        .join("vendor")
+        .join(name);
+    if vendored.join("CMakeLists.txt").exists() {
+        return vendored;
+    }
+    let cloned = cache_dir
+        .join("src")
+        .join(format!("{}-{}", name, IREE_REVISION));
+    with_cache_entry(&cloned, |dir| {
+        if dir.exists() {
+            // left over from an interrupted clone
+            std::fs::remove_dir_all(dir).expect("Unable to remove incomplete clone");
+        }
+        clone(dir);
+    });
+    cloned
+}
+
+/// 64-bit FNV-1a, a hash that is stable across Rust versions (unlike `DefaultHasher`).
+#[cfg(feature = "vendored")]
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Describes everything a cached build depends on: revisions, target, compiler and CMake options.
+#[cfg(feature = "vendored")]
+fn build_description(options: &[(&str, &str)]) -> String {
+    let compiler = Command::new("clang")
+        .arg("--version")
+        .output()
+        .ok()
+        .and_then(|output| {
+            String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .next()
+                .map(|line| line.to_string())
+        })
+        .unwrap_or_else(|| "unknown".to_string());
+    let mut description = format!(
+        "iree: {}\ntarget: {}\ncompiler: {}\n",
+        IREE_REVISION,
+        env::var("TARGET").unwrap(),
+        compiler
     );
+    for (key, value) in options.iter() {
+        description.push_str(&format!("-D{}={}\n", key, value));
+    }
+    description
+}
 
-    // clog
-    println!(
-        "cargo:rustc-link-search={}",
-        iree_build_dir
-            .join("build/iree_core/third_party/cpuinfo/deps/clog/")
-            .to_str()
-            .unwrap()
+/// Builds the IREE runtime from the pinned sources with the CMake project in `runtime-library/`.
+/// Returns the install prefix and the revision of the sources, if known.
+#[cfg(feature = "vendored")]
+fn clone_and_build_iree(out_dir: &Path) -> (PathBuf, Option<String>) {
+    let cache_dir = cache_dir(out_dir);
+    let iree_dir = source_dir("IREE_SOURCE_DIR", "iree", &cache_dir, clone_iree);
+    let revision = if skip_version_check() {
+        None
+    } else {
+        check_source_revision(&iree_dir)
+    };
+    let project_dir =
+        PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap()).join("runtime-library");
+    let project = std::fs::read(project_dir.join("CMakeLists.txt"))
+        .expect("Couldn't read runtime-library/CMakeLists.txt");
+
+    // build the runtime with only the drivers and loaders selected through features
+    let mut options = vec![
+        ("IREE_HAL_DRIVER_DEFAULTS", "OFF"),
+        ("IREE_HAL_EXECUTABLE_LOADER_DEFAULTS", "OFF"),
+        ("IREE_HAL_EXECUTABLE_PLUGIN_DEFAULTS", "OFF"),
+    ];
+    for (feature, feature_options) in HAL_FEATURES.iter() {
+        let value = if feature_enabled(feature) {
+            "ON"
+        } else {
+            "OFF"
+        };
+        for option in feature_options.iter() {
+            options.push((option, value));
+        }
+    }
+    options.extend([
+        ("BUILD_SHARED_LIBS", "OFF"),
+        ("CMAKE_C_COMPILER", "clang"),
+        ("CMAKE_CXX_COMPILER", "clang++"),
+    ]);
+
+    // sources given through the environment aren't pinned, so their builds are keyed by path too
+    let iree_dir = iree_dir.canonicalize().unwrap();
+    let description = format!(
+        "{}source: {}\nproject: {:016x}\n",
+        build_description(&options),
+        iree_dir.display(),
+        fnv1a(&project)
     );
-    let iree_include_dir = iree_build_dir.as_path().join("build/include");
+    let iree_build_path = cache_dir.join("build").join(format!(
+        "iree-{}-{:016x}",
+        IREE_REVISION,
+        fnv1a(description.as_bytes())
+    ));
 
-    println!("cargo:rustc-link-lib=iree");
+    with_cache_entry(&iree_build_path, |build_dir| {
+        std::fs::create_dir_all(build_dir).unwrap();
+        std::fs::write(build_dir.join("cache-key.txt"), &description).unwrap();
 
-    // third party libraries
-    println!("cargo:rustc-link-lib=cpuinfo");
-    println!("cargo:rustc-link-lib=flatcc_parsing");
-    println!("cargo:rustc-link-lib=clog");
-    println!("cargo:rustc-link-lib=stdc++");
+        let mut config = cmake::Config::new(&project_dir);
+        for (key, value) in options.iter() {
+            config.define(key, value);
+        }
+        config
+            .define("IREE_ROOT_DIR", iree_dir.to_str().unwrap())
+            // independent of the cargo profile, so debug and release builds share it
+            .profile("Release")
+            .out_dir(build_dir)
+            .build();
+    });
+
+    (iree_build_path, revision)
+}
 
-    // gather all api headers we want
-    let iree_api_headers = ["iree/runtime/api.h"];
+/// Libraries making up the IREE runtime, in link order.
+static IREE_RUNTIME_LIBS: &[&str] = &["iree", "cpuinfo", "flatcc_parsing", "clog"];
 
-    for &header in iree_api_headers.iter() {
-        let header_out = Path::new(header)
-            .to_str()
-            .and_then(|s| s.strip_suffix(".h"))
-            .and_then(|s| Some(format!("{}.rs", s)))
-            .unwrap();
+/// Headers and libraries of an IREE runtime build.
+struct IreeRuntime {
+    include_dir: PathBuf,
+    /// IREE revision or release version the runtime was built from, if known
+    revision: Option<String>,
+    link_dirs: Vec<PathBuf>,
+    /// libraries to link; empty if they were already emitted (e.g. by pkg-config)
+    link_libs: Vec<String>,
+}
+
+/// Libraries to link from a prebuilt runtime; `IREE_RUNTIME_LIBS` overrides the default set.
+fn runtime_libs() -> Vec<String> {
+    match env::var("IREE_RUNTIME_LIBS") {
+        Ok(libs) => libs
+            .split(|c| c == ',' || c == ' ')
+            .filter(|l| !l.is_empty())
+            .map(|l| l.to_string())
+            .collect(),
+        Err(_) => IREE_RUNTIME_LIBS.iter().map(|l| l.to_string()).collect(),
+    }
+}
+
+/// Looks for an existing IREE runtime build.
+///
+/// In order of precedence:
+/// - `IREE_DIR`: an install prefix containing `include/` and `lib/`
+/// - `IREE_RUNTIME_LIB_DIR` (and optionally `IREE_RUNTIME_INCLUDE_DIR`, defaulting to `../include`)
+/// - the `iree-runtime` pkg-config package
+fn find_prebuilt_iree() -> Option<IreeRuntime> {
+    if let Ok(iree_dir) = env::var("IREE_DIR") {
+        let iree_dir = PathBuf::from(iree_dir);
+        return Some(IreeRuntime {
+            include_dir: iree_dir.join("include"),
+            revision: None,
+            link_dirs: vec![iree_dir.join("lib")],
+            link_libs: runtime_libs(),
+        });
+    }
+
+    if let Ok(lib_dir) = env::var("IREE_RUNTIME_LIB_DIR") {
+        let lib_dir = PathBuf::from(lib_dir);
+        let include_dir = match env::var("IREE_RUNTIME_INCLUDE_DIR") {
+            Ok(include_dir) => PathBuf::from(include_dir),
+            Err(_) => lib_dir.join("../include"),
+        };
+        return Some(IreeRuntime {
+            include_dir,
+            revision: None,
+            link_dirs: vec![lib_dir],
+            link_libs: runtime_libs(),
+        });
+    }
 
-        if out_path.join(header_out.clone()).exists() {
-            // already generated
-            continue;
+    // with `dynamic` nothing is linked, so the package only provides the headers
+    match pkg_config::Config::new()
+        .statik(true)
+        .cargo_metadata(!cfg!(feature = "dynamic"))
+        .probe("iree-runtime")
+    {
+        Ok(library) => Some(IreeRuntime {
+            include_dir: library
+                .include_paths
+                .first()
+                .cloned()
+                .expect("pkg-config package iree-runtime has no include path"),
+            revision: Some(library.version),
+            link_dirs: Vec::new(),
+            link_libs: Vec::new(),
+        }),
+        Err(_) => None,
+    }
+}
+
+#[cfg(feature = "vendored")]
+fn vendored_iree(out_path: &Path) -> IreeRuntime {
+    let (iree_build_dir, revision) = clone_and_build_iree(out_path);
+    IreeRuntime {
+        include_dir: iree_build_dir.join("include"),
+        revision,
+        link_dirs: vec![iree_build_dir.join("lib")],
+        link_libs: IREE_RUNTIME_LIBS.iter().map(|l| l.to_string()).collect(),
+    }
+}
+
+#[cfg(not(feature = "vendored"))]
+fn vendored_iree(_out_path: &Path) -> IreeRuntime {
+    panic!(
+        "Could not find an IREE runtime build. Set IREE_DIR to an install prefix, \
+         IREE_RUNTIME_LIB_DIR (and IREE_RUNTIME_INCLUDE_DIR) to a runtime build, make the \
+         iree-runtime pkg-config package available, or enable the `vendored` feature to \
+         clone and build IREE from source."
+    )
+}
+
+/// A set of headers generated into one module under `OUT_DIR`. Only declarations from files
+/// matching `allowlist` are generated, everything else comes from the modules in `imports`.
+struct BindingUnit {
+    out: &'static str,
+    headers: Vec<&'static str>,
+    allowlist: Vec<&'static str>,
+    imports: &'static [&'static str],
+}
+
+fn binding_units() -> Vec<BindingUnit> {
+    let mut units = vec![
+        BindingUnit {
+            out: "iree/base/api.rs",
+            headers: vec!["iree/base/api.h"],
+            allowlist: vec![".*/iree/base/.*"],
+            imports: &[],
+        },
+        BindingUnit {
+            out: "iree/vm/api.rs",
+            headers: vec![
+                "iree/vm/api.h",
+                "iree/vm/bytecode/module.h|iree/vm/bytecode_module.h",
+            ],
+            allowlist: vec![".*/iree/vm/.*"],
+            imports: &["use crate::iree::base::api::*;"],
+        },
+        BindingUnit {
+            out: "iree/hal/api.rs",
+            headers: vec!["iree/hal/api.h"],
+            allowlist: vec![".*/iree/hal/[^/]*", ".*/iree/hal/utils/.*"],
+            imports: &["use crate::iree::base::api::*;"],
+        },
+        BindingUnit {
+            out: "iree/modules/hal/api.rs",
+            headers: vec!["iree/modules/hal/types.h", "iree/modules/hal/module.h"],
+            allowlist: vec![".*/iree/modules/hal/.*"],
+            imports: &[
+                "use crate::iree::base::api::*;",
+                "use crate::iree::hal::api::*;",
+                "use crate::iree::vm::api::*;",
+            ],
+        },
+        BindingUnit {
+            out: "iree/task/api.rs",
+            headers: vec!["iree/task/api.h"],
+            allowlist: vec![".*/iree/task/.*"],
+            imports: &["use crate::iree::base::api::*;"],
+        },
+        BindingUnit {
+            out: "iree/runtime/api.rs",
+            headers: vec!["iree/runtime/api.h"],
+            allowlist: vec![".*/iree/runtime/.*"],
+            // runtime::api has always exposed everything reachable from runtime/api.h
+            imports: &[
+                "pub use crate::iree::base::api::*;",
+                "pub use crate::iree::hal::api::*;",
+                "pub use crate::iree::modules::hal::api::*;",
+                "pub use crate::iree::vm::api::*;",
+            ],
+        },
+    ];
+
+    units.push(BindingUnit {
+        out: "iree/shim.rs",
+        headers: vec!["iree_sys_shim.h"],
+        allowlist: vec![".*/iree_sys_shim.h"],
+        imports: &[
+            "use crate::iree::base::api::*;",
+            "use crate::iree::vm::api::*;",
+        ],
+    });
+
+    // local HAL headers used to build CPU devices with executable plugins
+    let mut local_hal_headers = vec![
+        "iree/hal/local/executable_loader.h",
+        "iree/hal/local/executable_plugin_manager.h",
+    ];
+    if feature_enabled("loader-embedded-elf") {
+        local_hal_headers.push("iree/hal/local/loaders/embedded_elf_loader.h");
+        local_hal_headers.push("iree/hal/local/plugins/embedded_elf_plugin.h");
+    }
+    if feature_enabled("loader-system-library") {
+        local_hal_headers.push("iree/hal/local/loaders/system_library_loader.h");
+        local_hal_headers.push("iree/hal/local/plugins/system_library_plugin.h");
+    }
+    if feature_enabled("driver-local-sync") {
+        local_hal_headers.push("iree/hal/drivers/local_sync/sync_device.h");
+    }
+    units.push(BindingUnit {
+        out: "iree/hal/local/api.rs",
+        headers: local_hal_headers,
+        allowlist: vec![".*/iree/hal/local/.*", ".*/iree/hal/drivers/local_sync/.*"],
+        imports: &[
+            "use crate::iree::base::api::*;",
+            "use crate::iree::hal::api::*;",
+        ],
+    });
+
+    if feature_enabled("io") {
+        units.push(BindingUnit {
+            out: "iree/io/api.rs",
+            headers: vec![
+                "iree/io/file_handle.h",
+                "iree/io/parameter_index.h",
+                "iree/io/parameter_index_provider.h",
+                "iree/io/parameter_provider.h",
+                "iree/io/formats/parser_registry.h",
+                "iree/modules/io/parameters/module.h",
+            ],
+            allowlist: vec![".*/iree/io/.*", ".*/iree/modules/io/.*"],
+            imports: &[
+                "use crate::iree::base::api::*;",
+                "use crate::iree::hal::api::*;",
+                "use crate::iree::vm::api::*;",
+            ],
+        });
+    }
+
+    units
+}
+
+/// Shim functions implemented in Rust when the runtime is loaded at run time, because the C shim
+/// can't reference runtime symbols then.
+#[cfg(feature = "dynamic")]
+static SHIM_DYNAMIC_EXCLUDED: &[&str] = &["iree_sys_allocator_system"];
+
+/// Rewrites the extern functions of generated bindings into wrappers resolving their symbol from
+/// the runtime library loaded at run time (see `src/dynamic.rs`), and lists the symbols in
+/// `IREE_SYS_DYNAMIC_SYMBOLS` and the variadic functions left out in `IREE_SYS_DYNAMIC_UNSUPPORTED`.
+/// The shim is still linked statically, so for it only the functions it
+/// doesn't provide in this mode are removed.
+#[cfg(feature = "dynamic")]
+fn rewrite_dynamic_bindings(path: &Path, is_shim: bool) {
+    use quote::quote;
+    use syn::{FnArg, ForeignItem, Item, Pat, ReturnType, Type};
+
+    let source = std::fs::read_to_string(path).expect("Couldn't read bindings!");
+    if source.contains("IREE_SYS_DYNAMIC_SYMBOLS") {
+        // already rewritten
+        return;
+    }
+    let file = syn::parse_file(&source).expect("Couldn't parse bindings!");
+
+    let mut items = Vec::new();
+    let mut symbols = Vec::new();
+    let mut unsupported = Vec::new();
+    for item in file.items {
+        let mut foreign_mod = match item {
+            Item::ForeignMod(foreign_mod) => foreign_mod,
+            item => {
+                items.push(quote!(#item));
+                continue;
+            }
+        };
+        let mut kept = Vec::new();
+        for foreign_item in foreign_mod.items.drain(..) {
+            let function = match foreign_item {
+                ForeignItem::Fn(function) => function,
+                // e.g. stdout, which still comes from libc
+                foreign_item => {
+                    kept.push(foreign_item);
+                    continue;
+                }
+            };
+            let name = function.sig.ident.to_string();
+            if is_shim {
+                if !SHIM_DYNAMIC_EXCLUDED.contains(&name.as_str()) {
+                    kept.push(ForeignItem::Fn(function));
+                }
+                continue;
+            }
+            if function.sig.variadic.is_some() {
+                // variadic calls can't be forwarded in stable Rust, see dynamic::unsupported_symbols
+                unsupported.push(name);
+                continue;
+            }
+
+            let attrs = function
+                .attrs
+                .iter()
+                .filter(|attr| attr.path().is_ident("doc"));
+            let vis = &function.vis;
+            let ident = &function.sig.ident;
+            let inputs = &function.sig.inputs;
+            let output = &function.sig.output;
+            let (arg_names, arg_types): (Vec<_>, Vec<_>) = inputs
+                .iter()
+                .map(|arg| match arg {
+                    FnArg::Typed(arg) => match &*arg.pat {
+                        Pat::Ident(pat) => (pat.ident.clone(), arg.ty.clone()),
+                        _ => panic!("Unexpected argument in {}", name),
+                    },
+                    FnArg::Receiver(_) => panic!("Unexpected receiver in {}", name),
+                })
+                .unzip();
+            // functions returning a status report an unresolved symbol through it
+            let returns_status = match output {
+                ReturnType::Type(_, ty) => match &**ty {
+                    Type::Path(path) => path.path.is_ident("iree_status_t"),
+                    _ => false,
+                },
+                ReturnType::Default => false,
+            };
+            let unresolved = if returns_status {
+                quote!(return SYMBOL.unavailable(&err))
+            } else {
+                quote!(panic!("{}: {}", #name, err))
+            };
+            items.push(quote! {
+                #(#attrs)*
+                #[allow(clippy::missing_safety_doc)]
+                #vis unsafe fn #ident(#inputs) #output {
+                    static SYMBOL: crate::dynamic::Symbol = crate::dynamic::Symbol::new(#name);
+                    type Function = unsafe extern "C" fn(#(#arg_types),*) #output;
+                    let function = match SYMBOL.get() {
+                        Ok(ptr) => std::mem::transmute::<*mut std::ffi::c_void, Function>(ptr),
+                        Err(err) => #unresolved,
+                    };
+                    function(#(#arg_names),*)
+                }
+            });
+            symbols.push(name);
         }
-        let header_buf = iree_include_dir.join(header);
-        let header_path = header_buf.as_path();
+        if !kept.is_empty() {
+            foreign_mod.items = kept;
+            items.push(quote!(#foreign_mod));
+        }
+    }
 
-        let dir = out_path.join(Path::new(header).parent().unwrap());
+    let rewritten = quote! {
+        #(#items)*
+        pub(crate) static IREE_SYS_DYNAMIC_SYMBOLS: &[&str] = &[#(#symbols),*];
+        pub(crate) static IREE_SYS_DYNAMIC_UNSUPPORTED: &[&str] = &[#(#unsupported),*];
+    };
+    std::fs::write(path, rewritten.to_string()).expect("Couldn't write bindings!");
+}
+
+/// Checked-in bindings used by the `pregenerated` feature, relative to the crate root.
+static PREGENERATED_DIR: &str = "src/bindings";
 
-        if !dir.exists() {
-            std::fs::create_dir_all(&dir).expect("Unable to create directory");
+fn copy_pregenerated_bindings(unit: &BindingUnit, pregenerated_dir: &Path, out_dir: &Path) {
+    let src = pregenerated_dir.join(unit.out);
+    if !src.exists() {
+        panic!(
+            "Pre-generated bindings {} are missing. Build once without the `pregenerated` \
+             feature and with IREE_SYS_REGENERATE_BINDINGS=1 to create them (see src/bindings/README.md).",
+            src.display()
+        );
+    }
+    println!("cargo:rerun-if-changed={}", src.to_str().unwrap());
+    let out = out_dir.join(unit.out);
+    std::fs::create_dir_all(out.parent().unwrap()).expect("Unable to create directory");
+    std::fs::copy(src, out).expect("Couldn't copy pre-generated bindings!");
+}
+
+fn generate_bindings(unit: &BindingUnit, include_dirs: &[&Path], out_dir: &Path) {
+    let out = out_dir.join(unit.out);
+    if out.exists() {
+        // already generated
+        return;
+    }
+    std::fs::create_dir_all(out.parent().unwrap()).expect("Unable to create directory");
+
+    let mut contents = String::new();
+    for header in unit.headers.iter() {
+        // headers that moved between IREE versions are listed as `new|old`
+        let found = header
+            .split('|')
+            .find(|header| include_dirs.iter().any(|dir| dir.join(header).exists()));
+        match found {
+            Some(header) => contents.push_str(&format!("#include \"{}\"\n", header)),
+            None => panic!(
+                "{} not found in {}. The IREE runtime is too old for these bindings.",
+                header,
+                include_dirs[0].display()
+            ),
         }
+    }
 
-        let bindings = bindgen::Builder::default()
-            .header(header_path.to_str().unwrap())
-            .clang_arg(format!("-I{}", iree_include_dir.to_str().unwrap()))
-            .default_enum_style(bindgen::EnumVariation::NewType {
-                is_bitfield: true,
-                is_global: true,
-            })
-            .generate_inline_functions(false)
-            .derive_default(true)
-            .parse_callbacks(Box::new(bindgen::CargoCallbacks))
-            .generate()
-            .expect("Unable to generate bindings");
+    let mut builder = bindgen::Builder::default()
+        .header_contents("iree_sys.h", &contents)
+        .clang_args(
+            include_dirs
+                .iter()
+                .map(|dir| format!("-I{}", dir.to_str().unwrap())),
+        )
+        .default_enum_style(bindgen::EnumVariation::NewType {
+            is_bitfield: true,
+            is_global: true,
+        })
+        .generate_inline_functions(false)
+        .derive_default(true)
+        .parse_callbacks(Box::new(bindgen::CargoCallbacks));
+    for file in unit.allowlist.iter() {
+        builder = builder.allowlist_file(file);
+    }
+    if unit.imports.is_empty() {
+        // the base unit also owns the libc types the other headers use
+        builder = builder
+            .allowlist_type("FILE")
+            .allowlist_var("stdout|stderr");
+    } else {
+        builder = builder.allowlist_recursively(false);
+        for import in unit.imports.iter() {
+            builder = builder.raw_line(*import);
+        }
+    }
 
-        bindings
-            .write_to_file(out_path.join(header_out))
-            .expect("Couldn't write bindings!");
+    builder
+        .generate()
+        .expect("Unable to generate bindings")
+        .write_to_file(out)
+        .expect("Couldn't write bindings!");
+}
+
+fn main() {
+    for var in [
+        "IREE_DIR",
+        "IREE_RUNTIME_LIB_DIR",
+        "IREE_RUNTIME_INCLUDE_DIR",
+        "IREE_RUNTIME_LIBS",
+        "IREE_SOURCE_DIR",
+        "IREE_RUNTIME_REVISION",
+        "IREE_SYS_SKIP_VERSION_CHECK",
+        "IREE_SYS_REGENERATE_BINDINGS",
+        "IREE_SYS_CACHE_DIR",
+    ] {
+        println!("cargo:rerun-if-env-changed={}", var);
+    }
+
+    let out_path = PathBuf::from(env::var("OUT_DIR").unwrap());
+    let manifest_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+    let pregenerated = feature_enabled("pregenerated");
+    let iree_runtime = match find_prebuilt_iree() {
+        Some(iree_runtime) => Some(iree_runtime),
+        // checking and documenting the crate doesn't need a runtime
+        None if pregenerated && !cfg!(feature = "vendored") => None,
+        None => Some(vendored_iree(out_path.as_path())),
+    };
+    println!("cargo:rustc-env=IREE_SYS_IREE_REVISION={}", IREE_REVISION);
+
+    let shim_dir = manifest_dir.join("shim");
+    match &iree_runtime {
+        Some(iree_runtime) => {
+            // lets dependent crates (e.g. C shims or plugins) find the headers through DEP_IREE_INCLUDE
+            println!(
+                "cargo:include={}",
+                iree_runtime.include_dir.to_str().unwrap()
+            );
+            if !skip_version_check() {
+                check_runtime_revision(iree_runtime);
+            }
+
+            // the shim links against the runtime, so it has to come first on the link line
+            println!("cargo:rerun-if-changed={}", shim_dir.to_str().unwrap());
+            let mut shim = cc::Build::new();
+            shim.file(shim_dir.join("iree_sys_shim.c"))
+                .include(&iree_runtime.include_dir)
+                .include(&shim_dir);
+            if cfg!(feature = "dynamic") {
+                shim.define("IREE_SYS_DYNAMIC", None);
+            }
+            shim.compile("iree_sys_shim");
+
+            // with `dynamic` the runtime is loaded at run time instead, see src/dynamic.rs
+            if !cfg!(feature = "dynamic") {
+                for link_dir in iree_runtime.link_dirs.iter() {
+                    println!("cargo:rustc-link-search={}", link_dir.to_str().unwrap());
+                }
+                for lib in iree_runtime.link_libs.iter() {
+                    println!("cargo:rustc-link-lib={}", lib);
+                }
+                println!("cargo:rustc-link-lib=stdc++");
+            }
+        }
+        None => println!(
+            "cargo:warning=No IREE runtime found, using pre-generated bindings only. \
+             Linking binaries against iree-sys will fail."
+        ),
+    }
+
+    let (major, minor) = iree_runtime
+        .as_ref()
+        .map_or(IREE_VM_BYTECODE_VERSION, runtime_bytecode_version);
+    println!(
+        "cargo:rustc-env=IREE_SYS_VM_BYTECODE_VERSION_MAJOR={}",
+        major
+    );
+    println!(
+        "cargo:rustc-env=IREE_SYS_VM_BYTECODE_VERSION_MINOR={}",
+        minor
+    );
+
+    let pregenerated_dir = manifest_dir.join(PREGENERATED_DIR);
+    for unit in binding_units() {
+        if pregenerated {
+            copy_pregenerated_bindings(&unit, &pregenerated_dir, &out_path);
+        } else {
+            let include_dir = &iree_runtime.as_ref().unwrap().include_dir;
+            generate_bindings(&unit, &[include_dir, &shim_dir], &out_path);
+            if env::var("IREE_SYS_REGENERATE_BINDINGS").is_ok() {
+                let dest = pregenerated_dir.join(unit.out);
+                std::fs::create_dir_all(dest.parent().unwrap())
+                    .expect("Unable to create directory");
+                std::fs::copy(out_path.join(unit.out), dest)
+                    .expect("Couldn't update pre-generated bindings!");
+            }
+        }
+        #[cfg(feature = "dynamic")]
+        rewrite_dynamic_bindings(&out_path.join(unit.out), unit.out == "iree/shim.rs");
     }
 
     println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=runtime-library");
 }
@@ -1,4 +1,4 @@
-use iree_sys::{helper::*, iree::runtime::api::*};
+use iree_sys::{helper::*, iree::runtime::api::*, iree::shim::iree_sys_allocator_system};
 use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
 use std::{ffi::CString, ptr::null_mut, time::Instant};
@@ -207,10 +207,7 @@ pub mod test {
             );
             let mut instance: *mut iree_runtime_instance_t = null_mut();
 
-            let allocator = iree_allocator_t {
-                self_: null_mut(),
-                ctl: Some(iree_allocator_system_ctl as _),
-            };
+            let allocator = iree_sys_allocator_system();
 
             iree_runtime_instance_create(&instance_options, allocator, &mut instance as _);
 
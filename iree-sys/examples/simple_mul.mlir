@@ -0,0 +1,8 @@
+// Source of simple_mul_module.vmfb, used by the examples, tests and benches. Regenerate it with
+// the iree-compile of IREE_REVISION (iree-sys/build.rs) when the pinned revision changes:
+//   iree-compile --iree-hal-target-backends=vmvx simple_mul.mlir -o simple_mul_module.vmfb
+
+func.func @simple_mul(%arg0: tensor<4xf32>, %arg1: tensor<4xf32>) -> tensor<4xf32> {
+  %0 = arith.mulf %arg0, %arg1 : tensor<4xf32>
+  return %0 : tensor<4xf32>
+}
@@ -1,4 +1,4 @@
-use iree_sys::{helper::*, iree::runtime::api::*};
+use iree_sys::{helper::*, iree::runtime::api::*, iree::shim::iree_sys_allocator_system};
 use std::{ffi::CString, os::raw::c_char, ptr::null_mut};
 
 unsafe fn iree_runtime_demo_run_session(instance: *mut iree_runtime_instance_t) {
@@ -210,10 +210,7 @@ pub mod test {
             );
             let mut instance: *mut iree_runtime_instance_t = null_mut();
 
-            let allocator = iree_allocator_t {
-                self_: null_mut(),
-                ctl: Some(iree_allocator_system_ctl as _),
-            };
+            let allocator = iree_sys_allocator_system();
 
             iree_runtime_instance_create(&instance_options, allocator, &mut instance as _);
 
@@ -0,0 +1,46 @@
+#include "iree_sys_shim.h"
+
+#ifndef IREE_SYS_DYNAMIC
+// references iree_allocator_system_ctl, so a dynamically loaded runtime gets it from Rust instead
+iree_allocator_t iree_sys_allocator_system(void) { return iree_allocator_system(); }
+#endif  // IREE_SYS_DYNAMIC
+
+iree_allocator_t iree_sys_allocator_null(void) { return iree_allocator_null(); }
+
+iree_string_view_t iree_sys_make_string_view(const char* str, iree_host_size_t str_length) {
+  return iree_make_string_view(str, str_length);
+}
+
+iree_string_view_t iree_sys_make_cstring_view(const char* str) {
+  return iree_make_cstring_view(str);
+}
+
+iree_timeout_t iree_sys_infinite_timeout(void) { return iree_infinite_timeout(); }
+
+iree_status_code_t iree_sys_status_code(iree_status_t status) { return iree_status_code(status); }
+
+bool iree_sys_status_is_ok(iree_status_t status) { return iree_status_is_ok(status); }
+
+iree_status_t iree_sys_ok_status(void) { return iree_ok_status(); }
+
+iree_vm_value_t iree_sys_vm_value_make_none(void) { return iree_vm_value_make_none(); }
+
+iree_vm_value_t iree_sys_vm_value_make_i32(int32_t value) { return iree_vm_value_make_i32(value); }
+
+iree_vm_value_t iree_sys_vm_value_make_i64(int64_t value) { return iree_vm_value_make_i64(value); }
+
+iree_vm_value_t iree_sys_vm_value_make_f32(float value) { return iree_vm_value_make_f32(value); }
+
+iree_vm_value_t iree_sys_vm_value_make_f64(double value) { return iree_vm_value_make_f64(value); }
+
+int32_t iree_sys_vm_value_get_i32(iree_vm_value_t* value) { return iree_vm_value_get_i32(value); }
+
+int64_t iree_sys_vm_value_get_i64(iree_vm_value_t* value) { return iree_vm_value_get_i64(value); }
+
+float iree_sys_vm_value_get_f32(iree_vm_value_t* value) { return iree_vm_value_get_f32(value); }
+
+double iree_sys_vm_value_get_f64(iree_vm_value_t* value) { return iree_vm_value_get_f64(value); }
+
+iree_vm_ref_t iree_sys_vm_ref_null(void) { return iree_vm_ref_null(); }
+
+bool iree_sys_vm_ref_is_null(const iree_vm_ref_t* ref) { return iree_vm_ref_is_null(ref); }
@@ -0,0 +1,49 @@
+// Exported copies of IREE's inline functions and function-like macros, which bindgen cannot
+// generate bindings for.
+
+#ifndef IREE_SYS_SHIM_H_
+#define IREE_SYS_SHIM_H_
+
+#include "iree/base/api.h"
+#include "iree/vm/api.h"
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+// iree/base/allocator.h
+iree_allocator_t iree_sys_allocator_system(void);
+iree_allocator_t iree_sys_allocator_null(void);
+
+// iree/base/string_view.h
+iree_string_view_t iree_sys_make_string_view(const char* str, iree_host_size_t str_length);
+iree_string_view_t iree_sys_make_cstring_view(const char* str);
+
+// iree/base/time.h
+iree_timeout_t iree_sys_infinite_timeout(void);
+
+// iree/base/status.h
+iree_status_code_t iree_sys_status_code(iree_status_t status);
+bool iree_sys_status_is_ok(iree_status_t status);
+iree_status_t iree_sys_ok_status(void);
+
+// iree/vm/value.h
+iree_vm_value_t iree_sys_vm_value_make_none(void);
+iree_vm_value_t iree_sys_vm_value_make_i32(int32_t value);
+iree_vm_value_t iree_sys_vm_value_make_i64(int64_t value);
+iree_vm_value_t iree_sys_vm_value_make_f32(float value);
+iree_vm_value_t iree_sys_vm_value_make_f64(double value);
+int32_t iree_sys_vm_value_get_i32(iree_vm_value_t* value);
+int64_t iree_sys_vm_value_get_i64(iree_vm_value_t* value);
+float iree_sys_vm_value_get_f32(iree_vm_value_t* value);
+double iree_sys_vm_value_get_f64(iree_vm_value_t* value);
+
+// iree/vm/ref.h
+iree_vm_ref_t iree_sys_vm_ref_null(void);
+bool iree_sys_vm_ref_is_null(const iree_vm_ref_t* ref);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif
+
+#endif  // IREE_SYS_SHIM_H_
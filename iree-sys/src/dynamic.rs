@@ -0,0 +1,257 @@
+//! Loading the IREE runtime as a shared library at run time (`dynamic` feature).
+//!
+//! The bindings resolve their symbol from the loaded library on first call, so a runtime built
+//! without parts the bindings declare (e.g. the task system or `iree/io`) still works as long as
+//! those parts aren't used. Call [`load`] early to choose the library; otherwise the first call
+//! loads it from [`default_library_path`]. A binding returning `iree_status_t` whose symbol can't
+//! be resolved returns an `IREE_STATUS_UNAVAILABLE` status, any other binding panics.
+//!
+//! Variadic functions (e.g. `iree_status_allocate_f`) can't be forwarded by a Rust function and are
+//! left out of the bindings. They are listed in [`unsupported_symbols`] and can be resolved with
+//! [`symbol`] and called through a function pointer of the matching variadic type.
+
+use std::{
+    error,
+    ffi::{c_void, OsStr, OsString},
+    fmt::{self, Display},
+    sync::{
+        atomic::{AtomicPtr, Ordering},
+        OnceLock,
+    },
+};
+
+use crate::iree::base::api::{
+    iree_status_code_e_IREE_STATUS_UNAVAILABLE, iree_status_code_t, iree_status_t,
+    iree_string_view_t,
+};
+
+use libloading::Library;
+
+static LIBRARY: OnceLock<Library> = OnceLock::new();
+
+#[derive(Debug)]
+pub enum DynamicLoadError {
+    /// A runtime library was already loaded by an earlier call.
+    AlreadyLoaded,
+    Library(libloading::Error),
+    /// Symbols the bindings need that the library doesn't export, e.g. because it is too old.
+    MissingSymbols(Vec<String>),
+}
+
+impl Display for DynamicLoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DynamicLoadError::AlreadyLoaded => write!(f, "an IREE runtime is already loaded"),
+            DynamicLoadError::Library(err) => write!(f, "failed to load the IREE runtime: {}", err),
+            DynamicLoadError::MissingSymbols(symbols) => write!(
+                f,
+                "the IREE runtime is missing symbols: {}",
+                symbols.join(", ")
+            ),
+        }
+    }
+}
+
+impl error::Error for DynamicLoadError {}
+
+/// Symbols [`load`] requires: enough to create an instance and to report errors. Everything else is
+/// resolved when first called.
+const REQUIRED_SYMBOLS: &[&str] = &[
+    "iree_allocator_system_ctl",
+    "iree_status_allocate",
+    "iree_status_to_string",
+    "iree_status_ignore",
+    "iree_runtime_instance_create",
+];
+
+/// The symbols of each binding module, e.g. `("hal::local", [..])`.
+fn units() -> Vec<(
+    &'static str,
+    &'static [&'static str],
+    &'static [&'static str],
+)> {
+    use crate::iree::*;
+    vec![
+        (
+            "base",
+            base::api::IREE_SYS_DYNAMIC_SYMBOLS,
+            base::api::IREE_SYS_DYNAMIC_UNSUPPORTED,
+        ),
+        (
+            "vm",
+            vm::api::IREE_SYS_DYNAMIC_SYMBOLS,
+            vm::api::IREE_SYS_DYNAMIC_UNSUPPORTED,
+        ),
+        (
+            "hal",
+            hal::api::IREE_SYS_DYNAMIC_SYMBOLS,
+            hal::api::IREE_SYS_DYNAMIC_UNSUPPORTED,
+        ),
+        (
+            "hal::local",
+            hal::local::api::IREE_SYS_DYNAMIC_SYMBOLS,
+            hal::local::api::IREE_SYS_DYNAMIC_UNSUPPORTED,
+        ),
+        (
+            "modules::hal",
+            modules::hal::api::IREE_SYS_DYNAMIC_SYMBOLS,
+            modules::hal::api::IREE_SYS_DYNAMIC_UNSUPPORTED,
+        ),
+        (
+            "task",
+            task::api::IREE_SYS_DYNAMIC_SYMBOLS,
+            task::api::IREE_SYS_DYNAMIC_UNSUPPORTED,
+        ),
+        (
+            "runtime",
+            runtime::api::IREE_SYS_DYNAMIC_SYMBOLS,
+            runtime::api::IREE_SYS_DYNAMIC_UNSUPPORTED,
+        ),
+        #[cfg(feature = "io")]
+        (
+            "io",
+            io::api::IREE_SYS_DYNAMIC_SYMBOLS,
+            io::api::IREE_SYS_DYNAMIC_UNSUPPORTED,
+        ),
+    ]
+}
+
+/// The library loaded by default: `IREE_RUNTIME_LIBRARY` if set, otherwise `libiree_runtime` with the
+/// platform's prefix and suffix, searched for on the usual library path.
+pub fn default_library_path() -> OsString {
+    std::env::var_os("IREE_RUNTIME_LIBRARY")
+        .unwrap_or_else(|| libloading::library_filename("iree_runtime"))
+}
+
+/// Loads the runtime from `path`. Fails if the library can't be loaded or isn't an IREE runtime; use
+/// [`missing_symbols`] to check which parts of the bindings it doesn't provide.
+pub fn load<P: AsRef<OsStr>>(path: P) -> Result<(), DynamicLoadError> {
+    if LIBRARY.get().is_some() {
+        return Err(DynamicLoadError::AlreadyLoaded);
+    }
+    let library = unsafe { Library::new(path) }.map_err(DynamicLoadError::Library)?;
+    let missing = REQUIRED_SYMBOLS
+        .iter()
+        .copied()
+        .filter(|name| unsafe { library.get::<*mut c_void>(name.as_bytes()) }.is_err())
+        .map(str::to_string)
+        .collect::<Vec<_>>();
+    if !missing.is_empty() {
+        return Err(DynamicLoadError::MissingSymbols(missing));
+    }
+    LIBRARY
+        .set(library)
+        .map_err(|_| DynamicLoadError::AlreadyLoaded)
+}
+
+/// Loads the runtime from [`default_library_path`] unless one is loaded already.
+pub fn ensure_loaded() -> Result<(), DynamicLoadError> {
+    if LIBRARY.get().is_some() {
+        return Ok(());
+    }
+    match load(default_library_path()) {
+        Err(DynamicLoadError::AlreadyLoaded) => Ok(()),
+        result => result,
+    }
+}
+
+/// The symbols of each binding module (`"base"`, `"vm"`, `"hal"`, `"hal::local"`, `"modules::hal"`,
+/// `"task"`, `"runtime"` and with `io` `"io"`) the runtime doesn't export, leaving out modules it
+/// provides completely. Loads the runtime if needed.
+pub fn missing_symbols() -> Result<Vec<(&'static str, Vec<&'static str>)>, DynamicLoadError> {
+    ensure_loaded()?;
+    let library = LIBRARY.get().unwrap();
+    Ok(units()
+        .into_iter()
+        .map(|(unit, symbols, _)| {
+            let missing = symbols
+                .iter()
+                .copied()
+                .filter(|name| unsafe { library.get::<*mut c_void>(name.as_bytes()) }.is_err())
+                .collect::<Vec<_>>();
+            (unit, missing)
+        })
+        .filter(|(_, missing)| !missing.is_empty())
+        .collect())
+}
+
+/// Variadic functions declared by the headers, which the bindings leave out in this mode.
+pub fn unsupported_symbols() -> impl Iterator<Item = &'static str> {
+    units()
+        .into_iter()
+        .flat_map(|(_, _, unsupported)| unsupported.iter().copied())
+}
+
+/// Resolves `name` from the runtime, loading it if needed, e.g. to call one of the
+/// [`unsupported_symbols`].
+pub fn symbol(name: &str) -> Result<*mut c_void, DynamicLoadError> {
+    ensure_loaded()?;
+    let library = LIBRARY.get().unwrap();
+    match unsafe { library.get::<*mut c_void>(name.as_bytes()) } {
+        Ok(symbol) => Ok(*symbol),
+        Err(_) => Err(DynamicLoadError::MissingSymbols(vec![name.to_string()])),
+    }
+}
+
+/// Returns whether a runtime library has been loaded.
+pub fn is_loaded() -> bool {
+    LIBRARY.get().is_some()
+}
+
+/// A runtime symbol resolved on first use, used by the generated bindings.
+pub struct Symbol {
+    name: &'static str,
+    ptr: AtomicPtr<c_void>,
+}
+
+impl Symbol {
+    pub const fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            ptr: AtomicPtr::new(std::ptr::null_mut()),
+        }
+    }
+
+    pub fn get(&self) -> Result<*mut c_void, DynamicLoadError> {
+        let ptr = self.ptr.load(Ordering::Acquire);
+        if !ptr.is_null() {
+            return Ok(ptr);
+        }
+        let ptr = symbol(self.name)?;
+        self.ptr.store(ptr, Ordering::Release);
+        Ok(ptr)
+    }
+
+    /// The status returned in place of calling an unresolved function. It carries `err` as its
+    /// message if the runtime is loaded, and only the `IREE_STATUS_UNAVAILABLE` code otherwise.
+    pub fn unavailable(&self, err: &DynamicLoadError) -> iree_status_t {
+        type StatusAllocate = unsafe extern "C" fn(
+            iree_status_code_t,
+            *const std::os::raw::c_char,
+            u32,
+            iree_string_view_t,
+        ) -> iree_status_t;
+        let code = iree_status_code_e_IREE_STATUS_UNAVAILABLE;
+        let allocate = LIBRARY.get().and_then(|library| {
+            unsafe { library.get::<StatusAllocate>(b"iree_status_allocate") }.ok()
+        });
+        match allocate {
+            Some(allocate) => {
+                let message = format!("{}: {}", self.name, err);
+                unsafe {
+                    allocate(
+                        code,
+                        concat!(file!(), "\0").as_ptr() as _,
+                        line!(),
+                        iree_string_view_t {
+                            data: message.as_ptr() as _,
+                            size: message.len(),
+                        },
+                    )
+                }
+            }
+            // a status without payload is just its code
+            None => code.0 as usize as iree_status_t,
+        }
+    }
+}
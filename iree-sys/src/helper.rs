@@ -1,15 +1,13 @@
 use crate::iree::runtime::api::*;
+use crate::iree::shim::{iree_sys_allocator_system, iree_sys_status_is_ok};
 use std::{ffi::CString, ptr::null_mut};
 
 pub unsafe fn IREE_CHECK_OK(status: *mut iree_status_handle_t) -> bool {
-    return status == iree_status_code_e_IREE_STATUS_OK.0 as _;
+    return iree_sys_status_is_ok(status);
 }
 
 pub unsafe fn IREE_STATUS_TO_STRING(status: *mut iree_status_handle_t) -> String {
-    let host_allocator = iree_allocator_t {
-        self_: null_mut(),
-        ctl: Some(iree_allocator_system_ctl as _),
-    };
+    let host_allocator = iree_sys_allocator_system();
     let mut out_buffer: *mut i8 = null_mut();
     let mut out_buffer_length: usize = 0;
 
@@ -1 +1,9 @@
+pub mod base;
+pub mod hal;
+#[cfg(feature = "io")]
+pub mod io;
+pub mod modules;
 pub mod runtime;
+pub mod shim;
+pub mod task;
+pub mod vm;
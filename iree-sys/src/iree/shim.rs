@@ -0,0 +1,27 @@
+//! Exported copies of IREE's inline functions and macros (see `shim/iree_sys_shim.h`).
+#![allow(non_upper_case_globals)]
+#![allow(non_camel_case_types)]
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+
+include!(concat!(env!("OUT_DIR"), "/iree/shim.rs"));
+
+/// `iree_allocator_system()` for a dynamically loaded runtime. The system allocator's control
+/// function is resolved from the runtime on first use.
+#[cfg(feature = "dynamic")]
+pub unsafe fn iree_sys_allocator_system() -> iree_allocator_t {
+    iree_allocator_t {
+        self_: std::ptr::null_mut(),
+        ctl: Some(allocator_system_ctl),
+    }
+}
+
+#[cfg(feature = "dynamic")]
+unsafe extern "C" fn allocator_system_ctl(
+    self_: *mut std::os::raw::c_void,
+    command: iree_allocator_command_t,
+    params: *const std::os::raw::c_void,
+    inout_ptr: *mut *mut std::os::raw::c_void,
+) -> iree_status_t {
+    iree_allocator_system_ctl(self_, command, params, inout_ptr)
+}
@@ -1,2 +1,34 @@
+#[cfg(feature = "dynamic")]
+pub mod dynamic;
 pub mod helper;
 pub mod iree;
+
+/// HAL drivers compiled into the runtime, as selected by the `driver-*` features.
+pub const HAL_DRIVERS: &[&str] = &[
+    #[cfg(feature = "driver-local-sync")]
+    "local-sync",
+    #[cfg(feature = "driver-local-task")]
+    "local-task",
+];
+
+/// IREE revision the bindings were generated against.
+pub const IREE_REVISION: &str = env!("IREE_SYS_IREE_REVISION");
+
+/// VM bytecode version (major, minor) of the runtime. It loads modules with the same major and at
+/// most this minor version. Read from the runtime's headers where they define it, otherwise that of
+/// `IREE_REVISION`.
+pub const IREE_VM_BYTECODE_VERSION: (u16, u16) = (
+    parse_u16(env!("IREE_SYS_VM_BYTECODE_VERSION_MAJOR")),
+    parse_u16(env!("IREE_SYS_VM_BYTECODE_VERSION_MINOR")),
+);
+
+const fn parse_u16(digits: &str) -> u16 {
+    let digits = digits.as_bytes();
+    let mut value = 0;
+    let mut i = 0;
+    while i < digits.len() {
+        value = value * 10 + (digits[i] - b'0') as u16;
+        i += 1;
+    }
+    value
+}
@@ -0,0 +1,247 @@
+//! A HAL executable plugin exporting a CPU kernel written in Rust.
+//!
+//! Plugins are loaded by the local CPU devices and resolve imports of compiled executables. This crate
+//! does not link against IREE: it only implements the C ABI from `iree/hal/local/executable_plugin.h`.
+//! Executables compiled with an import of `simple_mul_workgroup` call into [`simple_mul_workgroup`].
+
+use std::os::raw::{c_char, c_int, c_void};
+
+pub const IREE_HAL_EXECUTABLE_PLUGIN_VERSION_0_1: u32 = 0x0000_0001;
+pub const IREE_HAL_EXECUTABLE_PLUGIN_VERSION_LATEST: u32 = IREE_HAL_EXECUTABLE_PLUGIN_VERSION_0_1;
+pub const IREE_HAL_EXECUTABLE_PLUGIN_FEATURE_STANDALONE: u32 = 1 << 0;
+pub const IREE_HAL_EXECUTABLE_PLUGIN_SANITIZER_NONE: u32 = 0;
+pub const IREE_HAL_EXECUTABLE_PLUGIN_RESOLUTION_MISSING_OPTIONAL: u32 = 1 << 0;
+pub const IREE_HAL_EXECUTABLE_PLUGIN_STATUS_NOT_FOUND: usize = 5;
+
+/// Status handle returned by plugin functions; null is OK, otherwise the value is a status code.
+pub type PluginStatus = *mut c_void;
+
+#[repr(C)]
+pub struct PluginHeader {
+    pub version: u32,
+    pub name: *const c_char,
+    pub description: *const c_char,
+    pub features: u32,
+    pub sanitizer: u32,
+}
+
+#[repr(C)]
+pub struct PluginAllocator {
+    pub ctl: *const c_void,
+    pub self_: *mut c_void,
+}
+
+#[repr(C)]
+pub struct PluginEnvironmentV0 {
+    pub host_allocator: PluginAllocator,
+}
+
+#[repr(C)]
+pub struct PluginStringView {
+    pub data: *const c_char,
+    pub size: usize,
+}
+
+#[repr(C)]
+pub struct PluginStringPair {
+    pub key: PluginStringView,
+    pub value: PluginStringView,
+}
+
+#[repr(C)]
+pub struct PluginResolveParamsV0 {
+    pub flags: u32,
+    pub count: usize,
+    pub symbol_names: *const *const c_char,
+    pub out_fn_ptrs: *mut *mut c_void,
+    pub out_fn_contexts: *mut *mut c_void,
+}
+
+#[repr(C)]
+pub struct PluginV0 {
+    pub header: *const PluginHeader,
+    pub load: unsafe extern "C" fn(
+        environment: *const PluginEnvironmentV0,
+        param_count: usize,
+        params: *const PluginStringPair,
+        out_self: *mut *mut c_void,
+    ) -> PluginStatus,
+    pub unload: unsafe extern "C" fn(self_: *mut c_void),
+    pub resolve: unsafe extern "C" fn(
+        self_: *mut c_void,
+        params: *const PluginResolveParamsV0,
+        out_resolution: *mut u32,
+    ) -> PluginStatus,
+}
+
+struct SyncHeader(PluginHeader);
+unsafe impl Sync for SyncHeader {}
+
+struct SyncPlugin(PluginV0);
+unsafe impl Sync for SyncPlugin {}
+
+static HEADER: SyncHeader = SyncHeader(PluginHeader {
+    version: IREE_HAL_EXECUTABLE_PLUGIN_VERSION_LATEST,
+    name: c"iree_rs_sample".as_ptr(),
+    description: c"sample plugin with CPU kernels written in Rust".as_ptr(),
+    features: IREE_HAL_EXECUTABLE_PLUGIN_FEATURE_STANDALONE,
+    sanitizer: IREE_HAL_EXECUTABLE_PLUGIN_SANITIZER_NONE,
+});
+
+static PLUGIN: SyncPlugin = SyncPlugin(PluginV0 {
+    header: &HEADER.0,
+    load: plugin_load,
+    unload: plugin_unload,
+    resolve: plugin_resolve,
+});
+
+/// Parameters of the `simple_mul_workgroup` import, as passed by the compiled executable.
+#[repr(C)]
+pub struct SimpleMulParams {
+    pub binding0: *const f32,
+    pub binding0_offset: usize,
+    pub binding1: *const f32,
+    pub binding1_offset: usize,
+    pub binding2: *mut f32,
+    pub binding2_offset: usize,
+    pub dim: usize,
+    pub tid: usize,
+}
+
+/// Element-wise multiply of 64 elements starting at `tid`.
+///
+/// # Safety
+/// `params_ptr` must point to a valid `SimpleMulParams` whose bindings cover `dim` elements.
+pub unsafe extern "C" fn simple_mul_workgroup(
+    params_ptr: *mut c_void,
+    _context: *mut c_void,
+    _reserved: *mut c_void,
+) -> c_int {
+    let params = &*(params_ptr as *const SimpleMulParams);
+    let end = (params.tid + 64).min(params.dim);
+    for i in params.tid..end {
+        *params.binding2.add(params.binding2_offset + i) =
+            *params.binding0.add(params.binding0_offset + i)
+                * *params.binding1.add(params.binding1_offset + i);
+    }
+    0
+}
+
+unsafe extern "C" fn plugin_load(
+    _environment: *const PluginEnvironmentV0,
+    _param_count: usize,
+    _params: *const PluginStringPair,
+    out_self: *mut *mut c_void,
+) -> PluginStatus {
+    // the plugin is stateless
+    *out_self = std::ptr::null_mut();
+    std::ptr::null_mut()
+}
+
+unsafe extern "C" fn plugin_unload(_self_: *mut c_void) {}
+
+unsafe extern "C" fn plugin_resolve(
+    _self_: *mut c_void,
+    params: *const PluginResolveParamsV0,
+    out_resolution: *mut u32,
+) -> PluginStatus {
+    let params = &*params;
+    *out_resolution = 0;
+    let mut any_required_not_found = false;
+    for i in 0..params.count {
+        if !(*params.out_fn_ptrs.add(i)).is_null() {
+            // already resolved by an earlier plugin
+            continue;
+        }
+        let mut symbol_name = std::ffi::CStr::from_ptr(*params.symbol_names.add(i)).to_bytes();
+        // optional imports are prefixed with '?'
+        let is_optional = symbol_name.first() == Some(&b'?');
+        if is_optional {
+            symbol_name = &symbol_name[1..];
+        }
+        match symbol_name {
+            b"simple_mul_workgroup" => {
+                *params.out_fn_ptrs.add(i) = simple_mul_workgroup as *mut c_void;
+                *params.out_fn_contexts.add(i) = std::ptr::null_mut();
+            }
+            _ if is_optional => {
+                *out_resolution |= IREE_HAL_EXECUTABLE_PLUGIN_RESOLUTION_MISSING_OPTIONAL;
+            }
+            _ => any_required_not_found = true,
+        }
+    }
+    if any_required_not_found {
+        IREE_HAL_EXECUTABLE_PLUGIN_STATUS_NOT_FOUND as PluginStatus
+    } else {
+        std::ptr::null_mut()
+    }
+}
+
+/// Entry point queried by the IREE runtime when the plugin is loaded.
+///
+/// # Safety
+/// Called by IREE with a valid (or null) environment pointer.
+#[no_mangle]
+pub unsafe extern "C" fn iree_hal_executable_plugin_query(
+    max_version: u32,
+    _environment: *const PluginEnvironmentV0,
+) -> *const *const PluginHeader {
+    if max_version < IREE_HAL_EXECUTABLE_PLUGIN_VERSION_0_1 {
+        return std::ptr::null();
+    }
+    // the header pointer is the first field of the plugin struct
+    &PLUGIN.0 as *const PluginV0 as *const *const PluginHeader
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_simple_mul_workgroup() {
+        let a = [1.0f32, 2.0, 3.0, 4.0];
+        let b = [2.0f32, 2.0, 2.0, 2.0];
+        let mut c = [0.0f32; 4];
+        let mut params = SimpleMulParams {
+            binding0: a.as_ptr(),
+            binding0_offset: 0,
+            binding1: b.as_ptr(),
+            binding1_offset: 0,
+            binding2: c.as_mut_ptr(),
+            binding2_offset: 0,
+            dim: 4,
+            tid: 0,
+        };
+        let ret = unsafe {
+            simple_mul_workgroup(
+                &mut params as *mut _ as *mut c_void,
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+            )
+        };
+        assert_eq!(ret, 0);
+        assert_eq!(c, [2.0, 4.0, 6.0, 8.0]);
+    }
+
+    #[test]
+    fn test_resolve() {
+        let names = [c"simple_mul_workgroup".as_ptr(), c"?missing".as_ptr()];
+        let mut fn_ptrs = [std::ptr::null_mut(); 2];
+        let mut fn_contexts = [std::ptr::null_mut(); 2];
+        let params = PluginResolveParamsV0 {
+            flags: 0,
+            count: 2,
+            symbol_names: names.as_ptr(),
+            out_fn_ptrs: fn_ptrs.as_mut_ptr(),
+            out_fn_contexts: fn_contexts.as_mut_ptr(),
+        };
+        let mut resolution = 0;
+        let status = unsafe { plugin_resolve(std::ptr::null_mut(), &params, &mut resolution) };
+        assert!(status.is_null());
+        assert!(!fn_ptrs[0].is_null());
+        assert_eq!(
+            resolution,
+            IREE_HAL_EXECUTABLE_PLUGIN_RESOLUTION_MISSING_OPTIONAL
+        );
+    }
+}
@@ -0,0 +1,617 @@
+//! Serves compiled modules over the KServe v2 inference protocol (HTTP/JSON):
+//!
+//! ```sh
+//! iree-server --address 127.0.0.1:8000 simple_mul_module.vmfb resnet=resnet18.vmfb#forward
+//! curl -X POST localhost:8000/v2/models/simple_mul_module/infer -d '{"inputs": [
+//!     {"name": "input0", "shape": [4], "datatype": "FP32", "data": [1, 2, 3, 4]},
+//!     {"name": "input1", "shape": [4], "datatype": "FP32", "data": [5, 6, 7, 8]}]}'
+//! ```
+//!
+//! Each module is loaded into its own session and serves one function, `#function` or the only
+//! public export. Inputs are named `input0`, `input1`, ... and outputs `output0`, ... by position.
+
+use std::process::ExitCode;
+
+use iree_rs::{
+    err::{IreeError, IreeErrorKind},
+    types::{
+        allocator::IreeAllocator,
+        bytespan::IreeConstByteSpan,
+        hal_buffer::{IreeHalBufferView, IreeHalBufferViewParamsBuilder},
+        hal_device::IreeHalDevice,
+        runtime::{
+            instance::{IreeRuntimeInstance, IreeRuntimeInstanceOptionsBuilder},
+            session::{IreeRuntimeSession, IreeRuntimeSessionOptionsBuilder},
+            signature::{IreeArgumentType, IreeFunctionSignature},
+        },
+        vm_module::IreeVmModule,
+    },
+    vmfb::module::IreeVmfbModule,
+};
+use iree_sys::iree::runtime::api::{
+    iree_hal_buffer_usage_bits_t_IREE_HAL_BUFFER_USAGE_DEFAULT, iree_hal_element_types_t,
+    iree_hal_element_types_t_IREE_HAL_ELEMENT_TYPE_BFLOAT_16,
+    iree_hal_element_types_t_IREE_HAL_ELEMENT_TYPE_BOOL_8,
+    iree_hal_element_types_t_IREE_HAL_ELEMENT_TYPE_FLOAT_16,
+    iree_hal_element_types_t_IREE_HAL_ELEMENT_TYPE_FLOAT_32,
+    iree_hal_element_types_t_IREE_HAL_ELEMENT_TYPE_FLOAT_64,
+    iree_hal_element_types_t_IREE_HAL_ELEMENT_TYPE_INT_16,
+    iree_hal_element_types_t_IREE_HAL_ELEMENT_TYPE_INT_32,
+    iree_hal_element_types_t_IREE_HAL_ELEMENT_TYPE_INT_64,
+    iree_hal_element_types_t_IREE_HAL_ELEMENT_TYPE_INT_8,
+    iree_hal_element_types_t_IREE_HAL_ELEMENT_TYPE_SINT_16,
+    iree_hal_element_types_t_IREE_HAL_ELEMENT_TYPE_SINT_32,
+    iree_hal_element_types_t_IREE_HAL_ELEMENT_TYPE_SINT_64,
+    iree_hal_element_types_t_IREE_HAL_ELEMENT_TYPE_SINT_8,
+    iree_hal_element_types_t_IREE_HAL_ELEMENT_TYPE_UINT_16,
+    iree_hal_element_types_t_IREE_HAL_ELEMENT_TYPE_UINT_32,
+    iree_hal_element_types_t_IREE_HAL_ELEMENT_TYPE_UINT_64,
+    iree_hal_element_types_t_IREE_HAL_ELEMENT_TYPE_UINT_8,
+    iree_hal_encoding_types_t_IREE_HAL_ENCODING_TYPE_DENSE_ROW_MAJOR,
+    iree_hal_memory_type_bits_t_IREE_HAL_MEMORY_TYPE_DEVICE_LOCAL, iree_runtime_call_flags_t,
+};
+use serde_json::{json, Value};
+use tiny_http::{Header, Method, Response, Server};
+
+const USAGE: &str = "usage: iree-server [--address <host:port>] [--driver <driver>] [<name>=]<module.vmfb>[#<function>]...";
+
+/// KServe datatypes and the element types they map to. Signless integers are read back as signed.
+const DATATYPES: &[(&str, iree_hal_element_types_t)] = &[
+    (
+        "BOOL",
+        iree_hal_element_types_t_IREE_HAL_ELEMENT_TYPE_BOOL_8,
+    ),
+    (
+        "UINT8",
+        iree_hal_element_types_t_IREE_HAL_ELEMENT_TYPE_UINT_8,
+    ),
+    (
+        "UINT16",
+        iree_hal_element_types_t_IREE_HAL_ELEMENT_TYPE_UINT_16,
+    ),
+    (
+        "UINT32",
+        iree_hal_element_types_t_IREE_HAL_ELEMENT_TYPE_UINT_32,
+    ),
+    (
+        "UINT64",
+        iree_hal_element_types_t_IREE_HAL_ELEMENT_TYPE_UINT_64,
+    ),
+    (
+        "INT8",
+        iree_hal_element_types_t_IREE_HAL_ELEMENT_TYPE_SINT_8,
+    ),
+    (
+        "INT16",
+        iree_hal_element_types_t_IREE_HAL_ELEMENT_TYPE_SINT_16,
+    ),
+    (
+        "INT32",
+        iree_hal_element_types_t_IREE_HAL_ELEMENT_TYPE_SINT_32,
+    ),
+    (
+        "INT64",
+        iree_hal_element_types_t_IREE_HAL_ELEMENT_TYPE_SINT_64,
+    ),
+    (
+        "FP16",
+        iree_hal_element_types_t_IREE_HAL_ELEMENT_TYPE_FLOAT_16,
+    ),
+    (
+        "FP32",
+        iree_hal_element_types_t_IREE_HAL_ELEMENT_TYPE_FLOAT_32,
+    ),
+    (
+        "FP64",
+        iree_hal_element_types_t_IREE_HAL_ELEMENT_TYPE_FLOAT_64,
+    ),
+    (
+        "BF16",
+        iree_hal_element_types_t_IREE_HAL_ELEMENT_TYPE_BFLOAT_16,
+    ),
+    ("INT8", iree_hal_element_types_t_IREE_HAL_ELEMENT_TYPE_INT_8),
+    (
+        "INT16",
+        iree_hal_element_types_t_IREE_HAL_ELEMENT_TYPE_INT_16,
+    ),
+    (
+        "INT32",
+        iree_hal_element_types_t_IREE_HAL_ELEMENT_TYPE_INT_32,
+    ),
+    (
+        "INT64",
+        iree_hal_element_types_t_IREE_HAL_ELEMENT_TYPE_INT_64,
+    ),
+];
+
+fn element_type(datatype: &str) -> Option<iree_hal_element_types_t> {
+    DATATYPES
+        .iter()
+        .find(|(name, _)| *name == datatype)
+        .map(|(_, element_type)| *element_type)
+}
+
+fn datatype(element_type: iree_hal_element_types_t) -> Option<&'static str> {
+    DATATYPES
+        .iter()
+        .find(|(_, t)| *t == element_type)
+        .map(|(name, _)| *name)
+}
+
+/// Maps an MLIR element type from reflection metadata, e.g. `f32` or `ui8`, to a KServe datatype.
+fn datatype_of_dtype(dtype: &str) -> Option<String> {
+    let datatype = match dtype {
+        "i1" => "BOOL".to_string(),
+        "f16" => "FP16".to_string(),
+        "bf16" => "BF16".to_string(),
+        "f32" => "FP32".to_string(),
+        "f64" => "FP64".to_string(),
+        dtype => match dtype.strip_prefix("ui") {
+            Some(bits) => format!("UINT{}", bits),
+            None => format!(
+                "INT{}",
+                dtype
+                    .strip_prefix("si")
+                    .or_else(|| dtype.strip_prefix('i'))?
+            ),
+        },
+    };
+    element_type(&datatype).map(|_| datatype)
+}
+
+/// Encodes numbers as native-endian elements, rejecting values that don't fit.
+fn encode<T, const N: usize>(
+    values: &[&Value],
+    parse: impl Fn(&Value) -> Option<T>,
+    to_bytes: impl Fn(T) -> [u8; N],
+) -> Result<Vec<u8>, String> {
+    let mut data = Vec::with_capacity(values.len() * N);
+    for value in values {
+        let value = parse(value).ok_or_else(|| format!("invalid element {}", value))?;
+        data.extend_from_slice(&to_bytes(value));
+    }
+    Ok(data)
+}
+
+fn encode_elements(datatype: &str, values: &[&Value]) -> Result<Vec<u8>, String> {
+    let int = |value: &Value| value.as_i64();
+    let uint = |value: &Value| value.as_u64();
+    match datatype {
+        "BOOL" => encode(values, |v| v.as_bool().map(u8::from), u8::to_ne_bytes),
+        "UINT8" => encode(values, |v| uint(v)?.try_into().ok(), u8::to_ne_bytes),
+        "UINT16" => encode(values, |v| uint(v)?.try_into().ok(), u16::to_ne_bytes),
+        "UINT32" => encode(values, |v| uint(v)?.try_into().ok(), u32::to_ne_bytes),
+        "UINT64" => encode(values, uint, u64::to_ne_bytes),
+        "INT8" => encode(values, |v| int(v)?.try_into().ok(), i8::to_ne_bytes),
+        "INT16" => encode(values, |v| int(v)?.try_into().ok(), i16::to_ne_bytes),
+        "INT32" => encode(values, |v| int(v)?.try_into().ok(), i32::to_ne_bytes),
+        "INT64" => encode(values, int, i64::to_ne_bytes),
+        "FP32" => encode(values, |v| v.as_f64().map(|v| v as f32), f32::to_ne_bytes),
+        "FP64" => encode(values, Value::as_f64, f64::to_ne_bytes),
+        #[cfg(feature = "half")]
+        "FP16" => encode(
+            values,
+            |v| v.as_f64().map(half::f16::from_f64),
+            half::f16::to_ne_bytes,
+        ),
+        #[cfg(feature = "half")]
+        "BF16" => encode(
+            values,
+            |v| v.as_f64().map(half::bf16::from_f64),
+            half::bf16::to_ne_bytes,
+        ),
+        datatype => Err(format!("datatype {} is not supported in JSON", datatype)),
+    }
+}
+
+fn decode<const N: usize>(data: &[u8], to_json: impl Fn([u8; N]) -> Value) -> Vec<Value> {
+    data.chunks_exact(N)
+        .map(|chunk| to_json(chunk.try_into().unwrap()))
+        .collect()
+}
+
+fn decode_elements(datatype: &str, data: &[u8]) -> Result<Vec<Value>, String> {
+    Ok(match datatype {
+        "BOOL" => decode(data, |b: [u8; 1]| json!(b[0] != 0)),
+        "UINT8" => decode(data, |b| json!(u8::from_ne_bytes(b))),
+        "UINT16" => decode(data, |b| json!(u16::from_ne_bytes(b))),
+        "UINT32" => decode(data, |b| json!(u32::from_ne_bytes(b))),
+        "UINT64" => decode(data, |b| json!(u64::from_ne_bytes(b))),
+        "INT8" => decode(data, |b| json!(i8::from_ne_bytes(b))),
+        "INT16" => decode(data, |b| json!(i16::from_ne_bytes(b))),
+        "INT32" => decode(data, |b| json!(i32::from_ne_bytes(b))),
+        "INT64" => decode(data, |b| json!(i64::from_ne_bytes(b))),
+        "FP32" => decode(data, |b| json!(f32::from_ne_bytes(b))),
+        "FP64" => decode(data, |b| json!(f64::from_ne_bytes(b))),
+        #[cfg(feature = "half")]
+        "FP16" => decode(data, |b| json!(half::f16::from_ne_bytes(b).to_f32())),
+        #[cfg(feature = "half")]
+        "BF16" => decode(data, |b| json!(half::bf16::from_ne_bytes(b).to_f32())),
+        datatype => return Err(format!("datatype {} is not supported in JSON", datatype)),
+    })
+}
+
+/// Flattens the (possibly nested) `data` of a tensor in row-major order.
+fn flatten<'a>(value: &'a Value, out: &mut Vec<&'a Value>) {
+    match value {
+        Value::Array(values) => values.iter().for_each(|value| flatten(value, out)),
+        value => out.push(value),
+    }
+}
+
+/// Tensor metadata for arguments or results, as far as the function's signature tells.
+fn tensor_metadata(prefix: &str, types: &[IreeArgumentType]) -> Value {
+    let tensors = types
+        .iter()
+        .enumerate()
+        .map(|(i, ty)| {
+            let name = format!("{}{}", prefix, i);
+            match ty {
+                IreeArgumentType::Tensor { dtype, shape } => json!({
+                    "name": name,
+                    "datatype": datatype_of_dtype(dtype).unwrap_or_else(|| dtype.clone()),
+                    "shape": shape
+                        .iter()
+                        .map(|dim| dim.map_or(-1, |dim| dim as i64))
+                        .collect::<Vec<_>>(),
+                }),
+                // scalars, or references without reflection metadata
+                ty => json!({
+                    "name": name,
+                    "datatype": datatype_of_dtype(&ty.to_string()).unwrap_or_else(|| ty.to_string()),
+                    "shape": [],
+                }),
+            }
+        })
+        .collect();
+    Value::Array(tensors)
+}
+
+struct Model {
+    name: String,
+    function: String,
+    session: IreeRuntimeSession,
+    signature: Option<IreeFunctionSignature>,
+}
+
+impl Model {
+    fn load(
+        instance: &IreeRuntimeInstance,
+        device: &IreeHalDevice,
+        spec: &str,
+    ) -> Result<Self, IreeError> {
+        let (name, path) = match spec.split_once('=') {
+            Some((name, path)) => (Some(name), path),
+            None => (None, spec),
+        };
+        let (path, function) = match path.split_once('#') {
+            Some((path, function)) => (path, Some(function)),
+            None => (path, None),
+        };
+        let data = std::fs::read(path)
+            .map_err(|err| IreeError::from(format!("failed to read {}: {}", path, err)))?;
+        let module = IreeVmfbModule::parse(&data)?;
+        let function = match function {
+            Some(function) => function.to_string(),
+            None => {
+                let exports = module
+                    .exported_functions()?
+                    .into_iter()
+                    .filter(|function| !function.name.starts_with("__"))
+                    .collect::<Vec<_>>();
+                match exports.as_slice() {
+                    [function] => function.name.to_string(),
+                    _ => {
+                        return Err(format!(
+                            "{} exports {} functions, select one with {}#<function>",
+                            path,
+                            exports.len(),
+                            path
+                        )
+                        .into())
+                    }
+                }
+            }
+        };
+        let name = match name {
+            Some(name) => name.to_string(),
+            None => std::path::Path::new(path)
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+                .unwrap_or_else(|| module.name().to_string()),
+        };
+
+        let allocator = instance.host_allocator();
+        let session = IreeRuntimeSession::create_with_device(
+            instance,
+            &IreeRuntimeSessionOptionsBuilder::default().build(),
+            device,
+            &allocator,
+        )?;
+        // the session keeps referencing the bytecode, so hand it a copy owned by IREE
+        session.append_module(&IreeVmModule::from_bytecode(instance, &data)?)?;
+        let function = format!("{}.{}", module.name(), function);
+        let signature = session.lookup_function(&function)?.signature().cloned();
+        Ok(Self {
+            name,
+            function,
+            session,
+            signature,
+        })
+    }
+
+    fn metadata(&self) -> Value {
+        let (inputs, outputs) = match &self.signature {
+            Some(signature) => (
+                tensor_metadata("input", &signature.arguments),
+                tensor_metadata("output", &signature.results),
+            ),
+            None => (json!([]), json!([])),
+        };
+        json!({
+            "name": self.name,
+            "versions": [],
+            "platform": "iree",
+            "inputs": inputs,
+            "outputs": outputs,
+        })
+    }
+
+    fn infer(&self, device: &IreeHalDevice, body: &str) -> Result<Value, (u16, String)> {
+        let bad_request = |msg: String| (400, msg);
+        let request: Value =
+            serde_json::from_str(body).map_err(|err| bad_request(err.to_string()))?;
+        let inputs = request["inputs"]
+            .as_array()
+            .ok_or_else(|| bad_request("missing inputs".to_string()))?;
+
+        // inputs are passed by position, so order them by the index in their name
+        let mut ordered = vec![None; inputs.len()];
+        for input in inputs {
+            let name = input["name"].as_str().unwrap_or_default();
+            match name
+                .strip_prefix("input")
+                .and_then(|i| i.parse::<usize>().ok())
+                .and_then(|i| ordered.get_mut(i))
+            {
+                Some(slot @ None) => *slot = Some(input),
+                _ => return Err(bad_request(format!("unexpected input {:?}", name))),
+            }
+        }
+
+        let mut call = self
+            .session
+            .get_call_by_name(&self.function)
+            .map_err(|err| (500, err.to_string()))?;
+        let params = IreeHalBufferViewParamsBuilder::default()
+            .type_(iree_hal_memory_type_bits_t_IREE_HAL_MEMORY_TYPE_DEVICE_LOCAL.0)
+            .usage(iree_hal_buffer_usage_bits_t_IREE_HAL_BUFFER_USAGE_DEFAULT.0)
+            .build();
+        for input in ordered.into_iter().flatten() {
+            let name = input["name"].as_str().unwrap_or_default();
+            let shape = input["shape"]
+                .as_array()
+                .and_then(|shape| {
+                    shape
+                        .iter()
+                        .map(|dim| dim.as_u64().map(|dim| dim as usize))
+                        .collect::<Option<Vec<_>>>()
+                })
+                .ok_or_else(|| bad_request(format!("{}: invalid shape", name)))?;
+            let datatype = input["datatype"].as_str().unwrap_or_default();
+            let element_type = element_type(datatype)
+                .ok_or_else(|| bad_request(format!("{}: unknown datatype {:?}", name, datatype)))?;
+            let mut values = Vec::new();
+            flatten(&input["data"], &mut values);
+            let count = shape.iter().product::<usize>();
+            if values.len() != count {
+                return Err(bad_request(format!(
+                    "{}: shape {:?} needs {} elements, got {}",
+                    name,
+                    shape,
+                    count,
+                    values.len()
+                )));
+            }
+            let data = encode_elements(datatype, &values)
+                .map_err(|msg| bad_request(format!("{}: {}", name, msg)))?;
+
+            let buffer_view = IreeHalBufferView::allocate_buffer_copy(
+                device,
+                &self.session.device_allocator(),
+                &shape,
+                element_type,
+                iree_hal_encoding_types_t_IREE_HAL_ENCODING_TYPE_DENSE_ROW_MAJOR,
+                &params,
+                &IreeConstByteSpan::from_slice(&data),
+            )
+            .map_err(|err| (500, err.to_string()))?;
+            call.inputs_push_back_buffer_view(&buffer_view)
+                .map_err(error_response)?;
+        }
+        call.invoke(iree_runtime_call_flags_t::default())
+            .map_err(error_response)?;
+
+        let requested = request["outputs"].as_array().map(|outputs| {
+            outputs
+                .iter()
+                .filter_map(|output| output["name"].as_str())
+                .collect::<Vec<_>>()
+        });
+        let mut outputs = Vec::new();
+        let mut i = 0;
+        while call.outputs_len() > 0 {
+            let name = format!("output{}", i);
+            i += 1;
+            let output = call
+                .outputs_pop_front_buffer_view()
+                .map_err(|err| (500, err.to_string()))?;
+            if requested
+                .as_ref()
+                .is_some_and(|requested| !requested.contains(&name.as_str()))
+            {
+                continue;
+            }
+            let datatype = datatype(output.element_type()).ok_or_else(|| {
+                (
+                    500,
+                    format!(
+                        "{}: unsupported element type {:#x}",
+                        name,
+                        output.element_type().0
+                    ),
+                )
+            })?;
+            let shape = output.shape().map_err(|err| (500, err.to_string()))?;
+            let data = output
+                .read_bytes(device)
+                .map_err(|err| (500, err.to_string()))?;
+            let data = decode_elements(datatype, &data).map_err(|msg| (500, msg))?;
+            outputs.push(json!({
+                "name": name,
+                "datatype": datatype,
+                "shape": shape,
+                "data": data,
+            }));
+        }
+
+        let mut response = json!({ "model_name": self.name, "outputs": outputs });
+        if let Some(id) = request.get("id") {
+            response["id"] = id.clone();
+        }
+        Ok(response)
+    }
+}
+
+/// Inputs not matching the function signature are the client's fault, anything else is ours.
+fn error_response(err: IreeError) -> (u16, String) {
+    match err.kind() {
+        IreeErrorKind::InvalidInput(..) => (400, err.to_string()),
+        _ => (500, err.to_string()),
+    }
+}
+
+fn route(
+    models: &[Model],
+    device: &IreeHalDevice,
+    method: &Method,
+    url: &str,
+    body: &str,
+) -> (u16, Value) {
+    let path = url.split('?').next().unwrap_or_default();
+    let segments = path
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .collect::<Vec<_>>();
+    let not_found = || (404, json!({ "error": format!("{} not found", path) }));
+    match (method, segments.as_slice()) {
+        (Method::Get, ["v2"]) => (
+            200,
+            json!({
+                "name": "iree-server",
+                "version": env!("CARGO_PKG_VERSION"),
+                "extensions": [],
+            }),
+        ),
+        // models are loaded before the server starts listening
+        (Method::Get, ["v2", "health", "live" | "ready"]) => (200, json!({})),
+        (method, ["v2", "models", name, rest @ ..]) => {
+            let Some(model) = models.iter().find(|model| model.name == *name) else {
+                return (404, json!({ "error": format!("unknown model {}", name) }));
+            };
+            // there is only one version of each model
+            let rest = match rest {
+                ["versions", _, rest @ ..] => rest,
+                rest => rest,
+            };
+            match (method, rest) {
+                (Method::Get, []) => (200, model.metadata()),
+                (Method::Get, ["ready"]) => (200, json!({})),
+                (Method::Post, ["infer"]) => match model.infer(device, body) {
+                    Ok(response) => (200, response),
+                    Err((status, msg)) => (status, json!({ "error": msg })),
+                },
+                _ => not_found(),
+            }
+        }
+        _ => not_found(),
+    }
+}
+
+fn serve(address: &str, driver: &str, specs: &[&str]) -> Result<(), IreeError> {
+    let instance = IreeRuntimeInstance::try_from_options(
+        &IreeRuntimeInstanceOptionsBuilder::default()
+            .use_all_available_drivers()
+            .build(),
+        &IreeAllocator::system_allocator(),
+    )?;
+    let device = instance.try_create_default_device(driver)?;
+    let models = specs
+        .iter()
+        .map(|spec| Model::load(&instance, &device, spec))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let server = Server::http(address)
+        .map_err(|err| IreeError::from(format!("failed to listen on {}: {}", address, err)))?;
+    for model in models.iter() {
+        println!("model {}: {}", model.name, model.function);
+    }
+    println!("listening on http://{}", server.server_addr());
+
+    let content_type = Header::from_bytes("Content-Type", "application/json").unwrap();
+    for mut request in server.incoming_requests() {
+        let mut body = String::new();
+        let (status, response) = match request.as_reader().read_to_string(&mut body) {
+            Ok(_) => route(&models, &device, request.method(), request.url(), &body),
+            Err(err) => (400, json!({ "error": err.to_string() })),
+        };
+        let response = Response::from_string(response.to_string())
+            .with_status_code(status)
+            .with_header(content_type.clone());
+        if let Err(err) = request.respond(response) {
+            eprintln!("failed to respond: {}", err);
+        }
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let mut address = "127.0.0.1:8000";
+    let mut driver = "local-task";
+    let mut specs = Vec::new();
+    let mut args = args.iter().map(String::as_str);
+    while let Some(arg) = args.next() {
+        let target = match arg {
+            "--address" => &mut address,
+            "--driver" => &mut driver,
+            arg if !arg.starts_with('-') => {
+                specs.push(arg);
+                continue;
+            }
+            _ => {
+                eprintln!("{}", USAGE);
+                return ExitCode::from(2);
+            }
+        };
+        match args.next() {
+            Some(value) => *target = value,
+            None => {
+                eprintln!("{}", USAGE);
+                return ExitCode::from(2);
+            }
+        }
+    }
+    if specs.is_empty() {
+        eprintln!("{}", USAGE);
+        return ExitCode::from(2);
+    }
+
+    match serve(address, driver, &specs) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
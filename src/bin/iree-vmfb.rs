@@ -0,0 +1,111 @@
+//! Inspects compiled modules without a runtime:
+//!
+//! ```sh
+//! iree-vmfb inspect model.vmfb
+//! iree-vmfb diff old.vmfb new.vmfb  # exits with 1 on breaking ABI changes
+//! ```
+
+use std::process::ExitCode;
+
+use iree_rs::{err::IreeError, vmfb::module::IreeVmfbModule};
+
+const USAGE: &str =
+    "usage: iree-vmfb inspect <module.vmfb>\n       iree-vmfb diff <old.vmfb> <new.vmfb>";
+
+fn read(path: &str) -> Result<Vec<u8>, IreeError> {
+    std::fs::read(path).map_err(|err| format!("failed to read {}: {}", path, err).into())
+}
+
+fn inspect(path: &str) -> Result<(), IreeError> {
+    let data = read(path)?;
+    let module = IreeVmfbModule::parse(&data)?;
+    let (major, minor) = module.bytecode_version()?;
+    println!("module: {}", module.name());
+    println!("version: {}", module.version()?);
+    println!("bytecode version: {}.{}", major, minor);
+    for (key, value) in module.attrs()? {
+        println!("attr: {} = {:?}", key, value);
+    }
+    for dependency in module.dependencies()? {
+        let optional = if dependency.optional {
+            " (optional)"
+        } else {
+            ""
+        };
+        println!(
+            "dependency: {} >= {}{}",
+            dependency.name, dependency.minimum_version, optional
+        );
+    }
+    for function in module.exported_functions()? {
+        println!("export: {} {}", function.name, function.signature);
+    }
+    for function in module.imported_functions()? {
+        let optional = if function.optional { " (optional)" } else { "" };
+        println!(
+            "import: {} {}{}",
+            function.name, function.signature, optional
+        );
+    }
+    for (i, segment) in module.rodata_segments()?.iter().enumerate() {
+        let storage = if segment.external {
+            "external"
+        } else {
+            "embedded"
+        };
+        println!("rodata {}: {} bytes {}", i, segment.data.len(), storage);
+    }
+    for executable in module.executables()? {
+        println!(
+            "executable: rodata {} {:?} {} bytes",
+            executable.segment,
+            executable.container,
+            executable.data.len()
+        );
+    }
+    let info = module.target_info()?;
+    println!("executable formats: {}", info.executable_formats.join(", "));
+    println!("cpu features: {}", info.cpu_features);
+    Ok(())
+}
+
+/// Prints ABI changes and returns whether any of them break callers.
+fn diff(old_path: &str, new_path: &str) -> Result<bool, IreeError> {
+    let old_data = read(old_path)?;
+    let new_data = read(new_path)?;
+    let old = IreeVmfbModule::parse(&old_data)?;
+    let new = IreeVmfbModule::parse(&new_data)?;
+
+    let mut breaking = false;
+    for change in new.abi_changes(&old)? {
+        let kind = if change.is_breaking() {
+            breaking = true;
+            "breaking"
+        } else {
+            "compatible"
+        };
+        println!("{}: {}", kind, change);
+    }
+    Ok(breaking)
+}
+
+fn main() -> ExitCode {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+    let result = match args.as_slice() {
+        ["inspect", path] => inspect(path).map(|_| false),
+        ["diff", old, new] => diff(old, new),
+        _ => {
+            eprintln!("{}", USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    match result {
+        Ok(false) => ExitCode::SUCCESS,
+        Ok(true) => ExitCode::FAILURE,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            ExitCode::from(2)
+        }
+    }
+}
@@ -1,6 +1,9 @@
 use std::{error, ffi::NulError, fmt::Display, string::FromUtf8Error};
 
-use crate::types::{allocator::IreeAllocator, status::IreeStatus};
+use crate::{
+    types::{allocator::IreeAllocator, status::IreeStatus},
+    vmfb::target::IreeVmfbIncompatibility,
+};
 
 /// Represents an error returned by IREE.
 /// IREE functions return a status code, which is a `u32` value. The IreeError struct assumes the status code is an error code.
@@ -13,8 +16,12 @@ pub struct IreeError {
 pub enum IreeErrorKind {
     Status(IreeStatus, String), // For when the function that returned the status code allocated a string for the error message
     UnallocatedStatus(IreeStatus), // For when the function that returned the status code did not allocate a string for the error message (e.g. when it doesn't have an allocator)
-    Other(Box<dyn error::Error>),  // For external errors
+    Other(Box<dyn error::Error + Send + Sync>), // For external errors
     Unknown(String),
+    InvalidVmfb(String), // For malformed module files
+    IncompatibleVmfb(String, Vec<IreeVmfbIncompatibility>), // Module name and why it can't be loaded
+    AmbiguousVmfbVariants(Vec<String>), // Labels of variants that can't be told apart
+    InvalidInput(String, String), // Function name and how the inputs don't match its signature
 }
 
 impl error::Error for IreeError {}
@@ -42,10 +49,22 @@ impl From<NulError> for IreeError {
     }
 }
 
+#[cfg(feature = "dynamic")]
+impl From<iree_sys::dynamic::DynamicLoadError> for IreeError {
+    fn from(e: iree_sys::dynamic::DynamicLoadError) -> Self {
+        Self {
+            kind: IreeErrorKind::Other(Box::new(e)),
+        }
+    }
+}
+
 impl IreeError {
     pub fn new(kind: IreeErrorKind) -> Self {
         Self { kind }
     }
+    pub fn kind(&self) -> &IreeErrorKind {
+        &self.kind
+    }
     pub fn from_status(status: IreeStatus, allocator: &IreeAllocator) -> Self {
         Self {
             kind: IreeErrorKind::Status(status, status.to_string(allocator).unwrap()),
@@ -60,6 +79,25 @@ impl Display for IreeError {
             IreeErrorKind::UnallocatedStatus(status) => write!(f, "IREE unallocated status: {:?} (try allocating the error message string using an allocator!)", status),
             IreeErrorKind::Unknown(msg) => write!(f, "IREE unknown error: {}", msg),
             IreeErrorKind::Other(err) => write!(f, "IREE other error: {}", err),
+            IreeErrorKind::InvalidVmfb(msg) => write!(f, "IREE invalid vmfb: {}", msg),
+            IreeErrorKind::IncompatibleVmfb(module, reasons) => write!(
+                f,
+                "IREE module {} is incompatible: {}",
+                module,
+                reasons
+                    .iter()
+                    .map(|reason| reason.to_string())
+                    .collect::<Vec<_>>()
+                    .join("; ")
+            ),
+            IreeErrorKind::AmbiguousVmfbVariants(labels) => write!(
+                f,
+                "IREE module variants {} have the same requirements, declare their CPU features to pick one",
+                labels.join(", ")
+            ),
+            IreeErrorKind::InvalidInput(function, msg) => {
+                write!(f, "IREE invalid input to {}: {}", function, msg)
+            }
         }
     }
 }
@@ -1,3 +1,4 @@
 pub mod err;
 pub mod ffi;
 pub mod types;
+pub mod vmfb;
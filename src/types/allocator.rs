@@ -1,4 +1,7 @@
-use iree_sys::{self, iree::runtime::api::iree_allocator_t};
+use iree_sys::iree::{
+    runtime::api::iree_allocator_t,
+    shim::{iree_sys_allocator_null, iree_sys_allocator_system},
+};
 
 pub struct IreeAllocator {
     pub(crate) allocator: iree_allocator_t,
@@ -7,12 +10,16 @@ pub struct IreeAllocator {
 impl IreeAllocator {
     /// Creates a default allocator that uses the system allocator (typically malloc).
     pub fn system_allocator() -> Self {
-        // FIXME: This emulates the functionality of the `iree_system_allocator` macro. We should ideally be able to use that macro directly.
         Self {
-            allocator: iree_allocator_t {
-                self_: std::ptr::null_mut(),
-                ctl: Some(iree_sys::iree::runtime::api::iree_allocator_system_ctl as _),
-            },
+            allocator: unsafe { iree_sys_allocator_system() },
+        }
+    }
+
+    /// An allocator that fails every allocation and ignores frees, for handing IREE memory it must
+    /// not free (e.g. module data borrowed from Rust).
+    pub fn null() -> Self {
+        Self {
+            allocator: unsafe { iree_sys_allocator_null() },
         }
     }
 }
@@ -1,4 +1,6 @@
-use iree_sys::iree::runtime::api::iree_const_byte_span_t;
+use iree_sys::iree::runtime::api::{iree_const_byte_span_t, iree_hal_element_types_t};
+
+use super::hal_element::IreeHalElement;
 
 pub struct IreeConstByteSpan<'a, T> {
     pub(crate) span: iree_const_byte_span_t,
@@ -16,3 +18,10 @@ impl<'a, T> IreeConstByteSpan<'a, T> {
         }
     }
 }
+
+impl<T: IreeHalElement> IreeConstByteSpan<'_, T> {
+    /// The element type to allocate buffers of `T` with.
+    pub fn element_type(&self) -> iree_hal_element_types_t {
+        T::ELEMENT_TYPE
+    }
+}
@@ -1,6 +1,128 @@
-use iree_sys::iree::runtime::api::iree_hal_allocator_t;
+use iree_sys::{
+    helper::IREE_CHECK_OK,
+    iree::runtime::api::{
+        iree_hal_allocator_query_statistics, iree_hal_allocator_statistics_format,
+        iree_hal_allocator_statistics_t, iree_hal_allocator_t, iree_hal_allocator_trim,
+        iree_string_builder_buffer, iree_string_builder_deinitialize,
+        iree_string_builder_initialize, iree_string_builder_size, iree_string_builder_t,
+    },
+};
+
+use crate::err::IreeError;
+
+use super::{allocator::IreeAllocator, status::IreeStatus};
 
 #[derive(Clone)]
 pub struct IreeHalAllocator {
     pub(crate) allocator_ptr: *mut iree_hal_allocator_t,
 }
+
+impl IreeHalAllocator {
+    /// Returns a snapshot of the allocation statistics of this allocator.
+    /// Statistics are only tracked if IREE was built with `IREE_STATISTICS_ENABLE`.
+    pub fn statistics(&self) -> IreeHalAllocatorStatistics {
+        let mut statistics = iree_hal_allocator_statistics_t::default();
+        unsafe {
+            iree_hal_allocator_query_statistics(self.allocator_ptr, &mut statistics);
+        }
+        IreeHalAllocatorStatistics::from(statistics)
+    }
+
+    /// Formats the current allocation statistics as a human readable report.
+    pub fn statistics_report(&self, allocator: &IreeAllocator) -> Result<String, IreeError> {
+        self.statistics().format(allocator)
+    }
+
+    /// Releases memory the allocator keeps cached, e.g. pooled blocks of freed buffers. This is a
+    /// no-op for the heap allocator of local devices, which frees buffers as they are released.
+    pub fn trim(&self) -> Result<(), IreeError> {
+        unsafe {
+            let status = iree_hal_allocator_trim(self.allocator_ptr);
+            if !IREE_CHECK_OK(status) {
+                return Err(IreeError::from_status(
+                    IreeStatus { status },
+                    &IreeAllocator::system_allocator(),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Byte counts for a single memory heap.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct IreeHalHeapStatistics {
+    pub bytes_allocated: u64,
+    pub bytes_freed: u64,
+    pub bytes_peak: u64,
+}
+
+impl IreeHalHeapStatistics {
+    /// Bytes currently allocated from this heap.
+    pub fn bytes_live(&self) -> u64 {
+        self.bytes_allocated.saturating_sub(self.bytes_freed)
+    }
+}
+
+/// Allocation statistics of an `IreeHalAllocator`, split by host and device heaps.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct IreeHalAllocatorStatistics {
+    pub host: IreeHalHeapStatistics,
+    pub device: IreeHalHeapStatistics,
+}
+
+impl From<iree_hal_allocator_statistics_t> for IreeHalAllocatorStatistics {
+    fn from(statistics: iree_hal_allocator_statistics_t) -> Self {
+        Self {
+            host: IreeHalHeapStatistics {
+                bytes_allocated: statistics.host_bytes_allocated as _,
+                bytes_freed: statistics.host_bytes_freed as _,
+                bytes_peak: statistics.host_bytes_peak as _,
+            },
+            device: IreeHalHeapStatistics {
+                bytes_allocated: statistics.device_bytes_allocated as _,
+                bytes_freed: statistics.device_bytes_freed as _,
+                bytes_peak: statistics.device_bytes_peak as _,
+            },
+        }
+    }
+}
+
+impl From<&IreeHalAllocatorStatistics> for iree_hal_allocator_statistics_t {
+    fn from(statistics: &IreeHalAllocatorStatistics) -> Self {
+        Self {
+            host_bytes_peak: statistics.host.bytes_peak as _,
+            host_bytes_allocated: statistics.host.bytes_allocated as _,
+            host_bytes_freed: statistics.host.bytes_freed as _,
+            device_bytes_peak: statistics.device.bytes_peak as _,
+            device_bytes_allocated: statistics.device.bytes_allocated as _,
+            device_bytes_freed: statistics.device.bytes_freed as _,
+        }
+    }
+}
+
+impl IreeHalAllocatorStatistics {
+    /// Formats the statistics using `iree_hal_allocator_statistics_format`.
+    pub fn format(&self, allocator: &IreeAllocator) -> Result<String, IreeError> {
+        let statistics = iree_hal_allocator_statistics_t::from(self);
+        let mut builder = iree_string_builder_t::default();
+        unsafe {
+            iree_string_builder_initialize(allocator.allocator, &mut builder);
+            let status = iree_hal_allocator_statistics_format(&statistics, &mut builder);
+            if !IREE_CHECK_OK(status) {
+                iree_string_builder_deinitialize(&mut builder);
+                return Err(IreeError::from_status(IreeStatus { status }, allocator));
+            }
+            let buffer_ptr = iree_string_builder_buffer(&builder) as *const u8;
+            let report = if buffer_ptr.is_null() {
+                String::new()
+            } else {
+                let buffer =
+                    std::slice::from_raw_parts(buffer_ptr, iree_string_builder_size(&builder));
+                String::from_utf8_lossy(buffer).to_string()
+            };
+            iree_string_builder_deinitialize(&mut builder);
+            Ok(report)
+        }
+    }
+}
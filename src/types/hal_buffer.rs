@@ -1,21 +1,32 @@
-use std::fmt::{Display, Error};
+use std::fmt::{Display, Error, Write};
 
 use iree_sys::{
     helper::IREE_CHECK_OK,
-    iree::runtime::api::{
-        iree_hal_buffer_params_t, iree_hal_buffer_usage_t,
-        iree_hal_buffer_view_allocate_buffer_copy, iree_hal_buffer_view_format,
-        iree_hal_buffer_view_release, iree_hal_buffer_view_shape, iree_hal_buffer_view_t,
-        iree_hal_device_t, iree_hal_dim_t, iree_hal_element_types_t, iree_hal_encoding_types_t,
-        iree_hal_memory_access_t, iree_hal_memory_type_t,
+    iree::{
+        runtime::api::{
+            iree_hal_buffer_params_t, iree_hal_buffer_usage_t,
+            iree_hal_buffer_view_allocate_buffer_copy, iree_hal_buffer_view_buffer,
+            iree_hal_buffer_view_byte_length, iree_hal_buffer_view_element_type,
+            iree_hal_buffer_view_format, iree_hal_buffer_view_release, iree_hal_buffer_view_shape,
+            iree_hal_buffer_view_t, iree_hal_device_t, iree_hal_device_transfer_d2h,
+            iree_hal_dim_t, iree_hal_element_types_t, iree_hal_encoding_types_t,
+            iree_hal_encoding_types_t_IREE_HAL_ENCODING_TYPE_DENSE_ROW_MAJOR,
+            iree_hal_memory_access_t, iree_hal_memory_type_t,
+            iree_hal_transfer_buffer_flag_bits_t_IREE_HAL_TRANSFER_BUFFER_FLAG_DEFAULT,
+        },
+        shim::iree_sys_infinite_timeout,
     },
 };
 
 use crate::err::IreeError;
 
 use super::{
-    allocator::IreeAllocator, bytespan::IreeConstByteSpan, hal_allocator::IreeHalAllocator,
-    hal_device::IreeHalDevice, status::IreeStatus,
+    allocator::IreeAllocator,
+    bytespan::IreeConstByteSpan,
+    hal_allocator::IreeHalAllocator,
+    hal_device::IreeHalDevice,
+    hal_element::{element_type_name, is_readable_as, IreeHalElement},
+    status::IreeStatus,
 };
 
 pub type IreeHalBufferShape = Vec<iree_hal_dim_t>;
@@ -63,6 +74,15 @@ pub struct IreeHalBufferView {
 }
 
 impl IreeHalBufferView {
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip_all,
+            fields(shape = ?shape, element_type = element_type.0, bytes = byte_span.span.data_length),
+            err
+        )
+    )]
     pub fn allocate_buffer_copy<T>(
         device: &IreeHalDevice,
         allocator: &IreeHalAllocator,
@@ -97,6 +117,73 @@ impl IreeHalBufferView {
             buffer_view_ptr: unsafe { buffer_view_ptr.assume_init() },
         })
     }
+
+    /// Copies `data` into a new dense row-major buffer of `shape`, with the element type of `T`.
+    pub fn from_elements<T: IreeHalElement>(
+        device: &IreeHalDevice,
+        allocator: &IreeHalAllocator,
+        shape: &IreeHalBufferShape,
+        params: &IreeHalBufferParams,
+        data: &[T],
+    ) -> Result<Self, IreeError> {
+        let element_count = shape.iter().product::<usize>();
+        if element_count != data.len() {
+            return Err(format!(
+                "shape {:?} has {} elements, got {}",
+                shape,
+                element_count,
+                data.len()
+            )
+            .into());
+        }
+        Self::allocate_buffer_copy(
+            device,
+            allocator,
+            shape,
+            T::ELEMENT_TYPE,
+            iree_hal_encoding_types_t_IREE_HAL_ENCODING_TYPE_DENSE_ROW_MAJOR,
+            params,
+            &IreeConstByteSpan::from_slice(data),
+        )
+    }
+
+    /// Reads the elements back to the host. `T` must match the element type of the buffer view;
+    /// signless integers (e.g. `i32` results of compiled modules) can be read as either signedness.
+    pub fn to_vec<T: IreeHalElement>(&self, device: &IreeHalDevice) -> Result<Vec<T>, IreeError> {
+        if !is_readable_as::<T>(self.element_type()) {
+            return Err(format!(
+                "cannot read {} elements as {}",
+                element_type_name(self.element_type()),
+                element_type_name(T::ELEMENT_TYPE)
+            )
+            .into());
+        }
+        let data = self.read_bytes(device)?;
+        Ok(data
+            .chunks_exact(std::mem::size_of::<T>())
+            .map(|chunk| unsafe { std::ptr::read_unaligned(chunk.as_ptr() as *const T) })
+            .collect())
+    }
+
+    /// Formats like `try_to_string` (e.g. `2x2xf16=[1 2][3 4]`), but on the host, for element types
+    /// the runtime can't format itself such as `f16` and `bf16`.
+    pub fn try_to_string_as<T: IreeHalElement + Display>(
+        &self,
+        device: &IreeHalDevice,
+        max_element_count: usize,
+    ) -> Result<String, IreeError> {
+        let shape = self.shape()?;
+        let elements = self.to_vec::<T>(device)?;
+        let mut out = String::new();
+        for dim in shape.iter() {
+            write!(out, "{}x", dim).unwrap();
+        }
+        write!(out, "{}=", element_type_name(self.element_type())).unwrap();
+        let mut remaining = max_element_count;
+        format_elements(&shape, &elements, &mut remaining, &mut out);
+        Ok(out)
+    }
+
     pub fn try_to_string(&self, max_element_count: usize) -> Result<String, IreeError> {
         let mut buffer = vec![0i8; max_element_count * 24]; // assume 24 bytes per element (maybe overkill)
         let mut out_buffer_length = std::mem::MaybeUninit::<usize>::uninit();
@@ -139,6 +226,81 @@ impl IreeHalBufferView {
         }
         return Ok(out_shape);
     }
+
+    /// Size of the buffer view's contents in bytes.
+    pub fn byte_length(&self) -> usize {
+        unsafe { iree_hal_buffer_view_byte_length(self.buffer_view_ptr) as usize }
+    }
+
+    /// Copies the contents of the buffer to host memory, waiting for the transfer to complete.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, fields(bytes = self.byte_length()), err)
+    )]
+    pub fn read_bytes(&self, device: &IreeHalDevice) -> Result<Vec<u8>, IreeError> {
+        let length = self.byte_length();
+        let mut data = vec![0u8; length];
+        unsafe {
+            let status = iree_hal_device_transfer_d2h(
+                device.device_ptr,
+                iree_hal_buffer_view_buffer(self.buffer_view_ptr),
+                0,
+                data.as_mut_ptr() as *mut _,
+                length as _,
+                iree_hal_transfer_buffer_flag_bits_t_IREE_HAL_TRANSFER_BUFFER_FLAG_DEFAULT.0,
+                iree_sys_infinite_timeout(),
+            );
+            if !IREE_CHECK_OK(status) {
+                return Err(IreeError::from_status(
+                    IreeStatus { status },
+                    &device.host_allocator(),
+                ));
+            }
+            Ok(data)
+        }
+    }
+
+    pub fn element_type(&self) -> iree_hal_element_types_t {
+        iree_hal_element_types_t(unsafe { iree_hal_buffer_view_element_type(self.buffer_view_ptr) })
+    }
+}
+
+/// Writes rows of elements nested in brackets by dimension. Ends with `...` once `remaining`
+/// elements were written, returning false so that no further rows are written.
+fn format_elements<T: Display>(
+    shape: &[usize],
+    elements: &[T],
+    remaining: &mut usize,
+    out: &mut String,
+) -> bool {
+    if shape.len() <= 1 {
+        for (i, element) in elements.iter().enumerate() {
+            if *remaining == 0 {
+                out.push_str("...");
+                return false;
+            }
+            if i > 0 {
+                out.push(' ');
+            }
+            write!(out, "{}", element).unwrap();
+            *remaining -= 1;
+        }
+        return true;
+    }
+    let row_length = elements.len() / shape[0].max(1);
+    for row in elements.chunks(row_length.max(1)) {
+        if *remaining == 0 {
+            out.push_str("...");
+            return false;
+        }
+        out.push('[');
+        let complete = format_elements(&shape[1..], row, remaining, out);
+        out.push(']');
+        if !complete {
+            return false;
+        }
+    }
+    true
 }
 
 impl Drop for IreeHalBufferView {
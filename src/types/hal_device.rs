@@ -1,9 +1,113 @@
-use iree_sys::iree::runtime::api::{iree_hal_device_release, iree_hal_device_t};
+#[cfg(all(feature = "driver-local-sync", feature = "loader-embedded-elf"))]
+use iree_sys::iree::hal::local::api::iree_hal_embedded_elf_loader_create;
+#[cfg(all(feature = "driver-local-sync", feature = "loader-system-library"))]
+use iree_sys::iree::hal::local::api::iree_hal_system_library_loader_create;
+#[cfg(all(
+    feature = "driver-local-sync",
+    any(feature = "loader-embedded-elf", feature = "loader-system-library")
+))]
+use iree_sys::iree::{
+    hal::local::api::{
+        iree_hal_executable_loader_release, iree_hal_executable_loader_t,
+        iree_hal_executable_plugin_manager_create,
+        iree_hal_executable_plugin_manager_register_plugin,
+        iree_hal_executable_plugin_manager_release, iree_hal_executable_plugin_manager_t,
+        iree_hal_sync_device_create, iree_hal_sync_device_params_initialize,
+        iree_hal_sync_device_params_t,
+    },
+    runtime::api::{
+        iree_hal_allocator_create_heap, iree_hal_allocator_release, iree_hal_allocator_t,
+        iree_status_t, iree_string_view_t,
+    },
+};
+use iree_sys::{
+    helper::IREE_CHECK_OK,
+    iree::{
+        runtime::api::{
+            iree_hal_device_host_allocator, iree_hal_device_id, iree_hal_device_query_i64,
+            iree_hal_device_release, iree_hal_device_t,
+        },
+        shim::iree_sys_make_string_view,
+    },
+};
+
+use crate::err::IreeError;
+
+use super::{allocator::IreeAllocator, runtime::call::view_to_string, status::IreeStatus};
+#[cfg(all(
+    feature = "driver-local-sync",
+    any(feature = "loader-embedded-elf", feature = "loader-system-library")
+))]
+use super::{
+    hal_executable_plugin::IreeHalExecutablePlugin, runtime::instance::IreeRuntimeInstance,
+};
 
 pub struct IreeHalDevice {
     pub(crate) device_ptr: *mut iree_hal_device_t,
+    pub(crate) driver: String,
+}
+
+impl IreeHalDevice {
+    /// Identifier of the device, e.g. `local-task`.
+    pub fn id(&self) -> String {
+        view_to_string(unsafe { iree_hal_device_id(self.device_ptr) })
+    }
+
+    /// Name of the driver that created the device, as registered with the runtime.
+    pub fn driver(&self) -> &str {
+        &self.driver
+    }
+
+    pub fn host_allocator(&self) -> IreeAllocator {
+        let allocator = unsafe { iree_hal_device_host_allocator(self.device_ptr) };
+        IreeAllocator { allocator }
+    }
+
+    /// Queries a device property, e.g. `("hal.device", "concurrency")`. Fails if the device doesn't
+    /// know the category or key.
+    pub fn query_i64(&self, category: &str, key: &str) -> Result<i64, IreeError> {
+        let mut value = 0;
+        unsafe {
+            let status = iree_hal_device_query_i64(
+                self.device_ptr,
+                iree_sys_make_string_view(category.as_ptr() as _, category.len()),
+                iree_sys_make_string_view(key.as_ptr() as _, key.len()),
+                &mut value,
+            );
+            if !IREE_CHECK_OK(status) {
+                return Err(IreeError::from_status(
+                    IreeStatus { status },
+                    &self.host_allocator(),
+                ));
+            }
+        }
+        Ok(value)
+    }
+
+    /// Whether the device can load executables of `format`, e.g. `embedded-elf-x86_64`.
+    pub fn supports_executable_format(&self, format: &str) -> Result<bool, IreeError> {
+        Ok(self.query_i64("hal.executable.format", format)? != 0)
+    }
+
+    /// Whether the host CPU of a local device has `feature`, e.g. `avx2`.
+    pub fn has_cpu_feature(&self, feature: &str) -> Result<bool, IreeError> {
+        Ok(self.query_i64("hal.cpu", feature)? != 0)
+    }
 }
 
+impl std::fmt::Debug for IreeHalDevice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("IreeHalDevice")
+            .field("id", &self.id())
+            .field("driver", &self.driver)
+            .finish()
+    }
+}
+
+// HAL devices are thread-safe and reference counted atomically
+unsafe impl Send for IreeHalDevice {}
+unsafe impl Sync for IreeHalDevice {}
+
 impl Drop for IreeHalDevice {
     fn drop(&mut self) {
         unsafe {
@@ -11,3 +115,203 @@ impl Drop for IreeHalDevice {
         }
     }
 }
+
+/// Executable loaders available to local CPU devices, as selected by the `loader-*` features.
+#[cfg(any(feature = "loader-embedded-elf", feature = "loader-system-library"))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IreeHalExecutableLoader {
+    #[cfg(feature = "loader-embedded-elf")]
+    EmbeddedElf,
+    #[cfg(feature = "loader-system-library")]
+    SystemLibrary,
+}
+
+#[cfg(any(feature = "loader-embedded-elf", feature = "loader-system-library"))]
+impl IreeHalExecutableLoader {
+    /// All loaders compiled into the runtime.
+    pub fn all() -> Vec<Self> {
+        vec![
+            #[cfg(feature = "loader-embedded-elf")]
+            IreeHalExecutableLoader::EmbeddedElf,
+            #[cfg(feature = "loader-system-library")]
+            IreeHalExecutableLoader::SystemLibrary,
+        ]
+    }
+}
+
+/// Builds a `local-sync` CPU device with an explicit set of executable loaders and plugins.
+/// Devices created with `IreeRuntimeInstance::try_create_default_device` cannot use plugins.
+#[cfg(all(
+    feature = "driver-local-sync",
+    any(feature = "loader-embedded-elf", feature = "loader-system-library")
+))]
+pub struct IreeHalLocalDeviceBuilder<'a> {
+    identifier: String,
+    loaders: Vec<IreeHalExecutableLoader>,
+    plugins: Vec<&'a IreeHalExecutablePlugin>,
+}
+
+#[cfg(all(
+    feature = "driver-local-sync",
+    any(feature = "loader-embedded-elf", feature = "loader-system-library")
+))]
+impl<'a> Default for IreeHalLocalDeviceBuilder<'a> {
+    fn default() -> Self {
+        Self {
+            identifier: "local-sync".to_string(),
+            loaders: Vec::new(),
+            plugins: Vec::new(),
+        }
+    }
+}
+
+#[cfg(all(
+    feature = "driver-local-sync",
+    any(feature = "loader-embedded-elf", feature = "loader-system-library")
+))]
+impl<'a> IreeHalLocalDeviceBuilder<'a> {
+    pub fn identifier(&mut self, identifier: &str) -> &mut Self {
+        self.identifier = identifier.to_string();
+        self
+    }
+
+    pub fn loader(&mut self, loader: IreeHalExecutableLoader) -> &mut Self {
+        if !self.loaders.contains(&loader) {
+            self.loaders.push(loader);
+        }
+        self
+    }
+
+    pub fn executable_plugin(&mut self, plugin: &'a IreeHalExecutablePlugin) -> &mut Self {
+        self.plugins.push(plugin);
+        self
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, fields(identifier = %self.identifier, plugins = self.plugins.len()), err)
+    )]
+    pub fn build(&self, instance: &IreeRuntimeInstance) -> Result<IreeHalDevice, IreeError> {
+        let host_allocator = instance.host_allocator();
+        let loaders = if self.loaders.is_empty() {
+            IreeHalExecutableLoader::all()
+        } else {
+            self.loaders.clone()
+        };
+
+        let mut plugin_manager: *mut iree_hal_executable_plugin_manager_t = std::ptr::null_mut();
+        let mut loader_ptrs: Vec<*mut iree_hal_executable_loader_t> = Vec::new();
+        let mut device_allocator: *mut iree_hal_allocator_t = std::ptr::null_mut();
+        let mut device_ptr: *mut iree_hal_device_t = std::ptr::null_mut();
+
+        let status = unsafe {
+            self.create_device(
+                &host_allocator,
+                &loaders,
+                &mut plugin_manager,
+                &mut loader_ptrs,
+                &mut device_allocator,
+                &mut device_ptr,
+            )
+        };
+
+        // the device retains everything it needs
+        unsafe {
+            for loader in loader_ptrs {
+                iree_hal_executable_loader_release(loader);
+            }
+            if !device_allocator.is_null() {
+                iree_hal_allocator_release(device_allocator);
+            }
+            if !plugin_manager.is_null() {
+                iree_hal_executable_plugin_manager_release(plugin_manager);
+            }
+            if !IREE_CHECK_OK(status) {
+                return Err(IreeError::from_status(
+                    IreeStatus { status },
+                    &host_allocator,
+                ));
+            }
+        }
+        Ok(IreeHalDevice {
+            device_ptr,
+            driver: "local-sync".to_string(),
+        })
+    }
+
+    unsafe fn create_device(
+        &self,
+        host_allocator: &IreeAllocator,
+        loaders: &[IreeHalExecutableLoader],
+        plugin_manager: &mut *mut iree_hal_executable_plugin_manager_t,
+        loader_ptrs: &mut Vec<*mut iree_hal_executable_loader_t>,
+        device_allocator: &mut *mut iree_hal_allocator_t,
+        device_ptr: &mut *mut iree_hal_device_t,
+    ) -> iree_status_t {
+        let status = iree_hal_executable_plugin_manager_create(
+            self.plugins.len(),
+            host_allocator.allocator,
+            plugin_manager,
+        );
+        if !IREE_CHECK_OK(status) {
+            return status;
+        }
+        for plugin in self.plugins.iter() {
+            let status = iree_hal_executable_plugin_manager_register_plugin(
+                *plugin_manager,
+                plugin.plugin_ptr,
+            );
+            if !IREE_CHECK_OK(status) {
+                return status;
+            }
+        }
+
+        for loader in loaders {
+            let mut loader_ptr: *mut iree_hal_executable_loader_t = std::ptr::null_mut();
+            let status = match loader {
+                #[cfg(feature = "loader-embedded-elf")]
+                IreeHalExecutableLoader::EmbeddedElf => iree_hal_embedded_elf_loader_create(
+                    *plugin_manager,
+                    host_allocator.allocator,
+                    &mut loader_ptr,
+                ),
+                #[cfg(feature = "loader-system-library")]
+                IreeHalExecutableLoader::SystemLibrary => iree_hal_system_library_loader_create(
+                    *plugin_manager,
+                    host_allocator.allocator,
+                    &mut loader_ptr,
+                ),
+            };
+            if !IREE_CHECK_OK(status) {
+                return status;
+            }
+            loader_ptrs.push(loader_ptr);
+        }
+
+        let identifier = iree_string_view_t {
+            data: self.identifier.as_ptr() as _,
+            size: self.identifier.len(),
+        };
+        let status = iree_hal_allocator_create_heap(
+            identifier,
+            host_allocator.allocator,
+            host_allocator.allocator,
+            device_allocator,
+        );
+        if !IREE_CHECK_OK(status) {
+            return status;
+        }
+
+        let mut params = iree_hal_sync_device_params_t::default();
+        iree_hal_sync_device_params_initialize(&mut params);
+        iree_hal_sync_device_create(
+            identifier,
+            &params,
+            loader_ptrs.len(),
+            loader_ptrs.as_mut_ptr(),
+            *device_allocator,
+            host_allocator.allocator,
+            device_ptr,
+        )
+    }
+}
@@ -0,0 +1,81 @@
+use iree_sys::iree::runtime::api::{
+    iree_hal_element_types_t, iree_hal_element_types_t_IREE_HAL_ELEMENT_TYPE_FLOAT_32,
+    iree_hal_element_types_t_IREE_HAL_ELEMENT_TYPE_FLOAT_64,
+    iree_hal_element_types_t_IREE_HAL_ELEMENT_TYPE_SINT_16,
+    iree_hal_element_types_t_IREE_HAL_ELEMENT_TYPE_SINT_32,
+    iree_hal_element_types_t_IREE_HAL_ELEMENT_TYPE_SINT_64,
+    iree_hal_element_types_t_IREE_HAL_ELEMENT_TYPE_SINT_8,
+    iree_hal_element_types_t_IREE_HAL_ELEMENT_TYPE_UINT_16,
+    iree_hal_element_types_t_IREE_HAL_ELEMENT_TYPE_UINT_32,
+    iree_hal_element_types_t_IREE_HAL_ELEMENT_TYPE_UINT_64,
+    iree_hal_element_types_t_IREE_HAL_ELEMENT_TYPE_UINT_8,
+};
+#[cfg(feature = "half")]
+use iree_sys::iree::runtime::api::{
+    iree_hal_element_types_t_IREE_HAL_ELEMENT_TYPE_BFLOAT_16,
+    iree_hal_element_types_t_IREE_HAL_ELEMENT_TYPE_FLOAT_16,
+};
+
+/// A host type stored in buffers as the HAL element type `ELEMENT_TYPE`, e.g. `f32` as `FLOAT_32`.
+///
+/// # Safety
+///
+/// `Self` must have the size of the element type, and every bit pattern of that size must be a valid
+/// value, since buffer contents are read back as `Self`.
+pub unsafe trait IreeHalElement: Copy {
+    const ELEMENT_TYPE: iree_hal_element_types_t;
+}
+
+macro_rules! element {
+    ($($ty:ty => $element_type:ident),* $(,)?) => {
+        $(
+            unsafe impl IreeHalElement for $ty {
+                const ELEMENT_TYPE: iree_hal_element_types_t = $element_type;
+            }
+        )*
+    };
+}
+
+element!(
+    i8 => iree_hal_element_types_t_IREE_HAL_ELEMENT_TYPE_SINT_8,
+    i16 => iree_hal_element_types_t_IREE_HAL_ELEMENT_TYPE_SINT_16,
+    i32 => iree_hal_element_types_t_IREE_HAL_ELEMENT_TYPE_SINT_32,
+    i64 => iree_hal_element_types_t_IREE_HAL_ELEMENT_TYPE_SINT_64,
+    u8 => iree_hal_element_types_t_IREE_HAL_ELEMENT_TYPE_UINT_8,
+    u16 => iree_hal_element_types_t_IREE_HAL_ELEMENT_TYPE_UINT_16,
+    u32 => iree_hal_element_types_t_IREE_HAL_ELEMENT_TYPE_UINT_32,
+    u64 => iree_hal_element_types_t_IREE_HAL_ELEMENT_TYPE_UINT_64,
+    f32 => iree_hal_element_types_t_IREE_HAL_ELEMENT_TYPE_FLOAT_32,
+    f64 => iree_hal_element_types_t_IREE_HAL_ELEMENT_TYPE_FLOAT_64,
+);
+
+#[cfg(feature = "half")]
+element!(
+    half::f16 => iree_hal_element_types_t_IREE_HAL_ELEMENT_TYPE_FLOAT_16,
+    half::bf16 => iree_hal_element_types_t_IREE_HAL_ELEMENT_TYPE_BFLOAT_16,
+);
+
+/// Name of an element type in MLIR notation, e.g. `f32` or `si8`.
+pub fn element_type_name(element_type: iree_hal_element_types_t) -> String {
+    // iree_hal_element_type_t packs the numerical type into the high byte and the bit count into the low byte
+    let bits = element_type.0 & 0xFF;
+    match (element_type.0 >> 24) & 0xFF {
+        0x10 => format!("i{}", bits),
+        0x11 => format!("si{}", bits),
+        0x12 => format!("ui{}", bits),
+        0x13 => "i1".to_string(),
+        0x21 => format!("f{}", bits),
+        0x22 => format!("bf{}", bits),
+        0x23 => format!("complex<f{}>", bits / 2),
+        _ => format!("opaque{}", bits),
+    }
+}
+
+/// Whether elements of `element_type` can be read as `T`: either its own element type, or for
+/// integers a signless type of the same width, which is what compiled modules return for `i32`.
+pub(crate) fn is_readable_as<T: IreeHalElement>(element_type: iree_hal_element_types_t) -> bool {
+    let signless = (element_type.0 >> 24) & 0xFF == 0x10;
+    let integer = matches!((T::ELEMENT_TYPE.0 >> 24) & 0xFF, 0x11 | 0x12);
+    let same_width = element_type.0 & 0xFF == T::ELEMENT_TYPE.0 & 0xFF;
+    element_type == T::ELEMENT_TYPE || (signless && integer && same_width)
+}
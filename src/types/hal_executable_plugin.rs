@@ -0,0 +1,110 @@
+use std::ffi::CString;
+
+use iree_sys::{
+    helper::IREE_CHECK_OK,
+    iree::{
+        hal::local::api::{iree_hal_executable_plugin_release, iree_hal_executable_plugin_t},
+        runtime::api::{
+            iree_string_pair_t, iree_string_pair_t__bindgen_ty_1, iree_string_pair_t__bindgen_ty_2,
+            iree_string_view_t,
+        },
+    },
+};
+
+#[cfg(feature = "loader-embedded-elf")]
+use iree_sys::iree::hal::local::api::iree_hal_embedded_elf_executable_plugin_load_from_file;
+#[cfg(feature = "loader-system-library")]
+use iree_sys::iree::hal::local::api::iree_hal_system_executable_plugin_load_from_file;
+
+use crate::err::IreeError;
+
+use super::{allocator::IreeAllocator, status::IreeStatus};
+
+/// An executable plugin providing imports (e.g. hand written kernels) to CPU executables.
+pub struct IreeHalExecutablePlugin {
+    pub(crate) plugin_ptr: *mut iree_hal_executable_plugin_t,
+}
+
+fn string_pairs(params: &[(&str, &str)]) -> Vec<iree_string_pair_t> {
+    params
+        .iter()
+        .map(|(key, value)| iree_string_pair_t {
+            __bindgen_anon_1: iree_string_pair_t__bindgen_ty_1 {
+                key: iree_string_view_t {
+                    data: key.as_ptr() as _,
+                    size: key.len(),
+                },
+            },
+            __bindgen_anon_2: iree_string_pair_t__bindgen_ty_2 {
+                value: iree_string_view_t {
+                    data: value.as_ptr() as _,
+                    size: value.len(),
+                },
+            },
+        })
+        .collect()
+}
+
+impl IreeHalExecutablePlugin {
+    /// Loads a plugin from a shared library (`.so`/`.dylib`/`.dll`) exporting `iree_hal_executable_plugin_query`.
+    #[cfg(feature = "loader-system-library")]
+    pub fn load_system_library(
+        path: &str,
+        params: &[(&str, &str)],
+        allocator: &IreeAllocator,
+    ) -> Result<Self, IreeError> {
+        let path = CString::new(path)?;
+        let params = string_pairs(params);
+        let mut plugin_ptr = std::mem::MaybeUninit::<*mut iree_hal_executable_plugin_t>::uninit();
+        unsafe {
+            let status = iree_hal_system_executable_plugin_load_from_file(
+                path.as_ptr(),
+                params.len(),
+                params.as_ptr(),
+                allocator.allocator,
+                plugin_ptr.as_mut_ptr(),
+            );
+            if !IREE_CHECK_OK(status) {
+                return Err(IreeError::from_status(IreeStatus { status }, allocator));
+            }
+        }
+        Ok(Self {
+            plugin_ptr: unsafe { plugin_ptr.assume_init() },
+        })
+    }
+
+    /// Loads a plugin from a platform-independent embedded ELF file.
+    #[cfg(feature = "loader-embedded-elf")]
+    pub fn load_embedded_elf(
+        path: &str,
+        params: &[(&str, &str)],
+        allocator: &IreeAllocator,
+    ) -> Result<Self, IreeError> {
+        let path = CString::new(path)?;
+        let params = string_pairs(params);
+        let mut plugin_ptr = std::mem::MaybeUninit::<*mut iree_hal_executable_plugin_t>::uninit();
+        unsafe {
+            let status = iree_hal_embedded_elf_executable_plugin_load_from_file(
+                path.as_ptr(),
+                params.len(),
+                params.as_ptr(),
+                allocator.allocator,
+                plugin_ptr.as_mut_ptr(),
+            );
+            if !IREE_CHECK_OK(status) {
+                return Err(IreeError::from_status(IreeStatus { status }, allocator));
+            }
+        }
+        Ok(Self {
+            plugin_ptr: unsafe { plugin_ptr.assume_init() },
+        })
+    }
+}
+
+impl Drop for IreeHalExecutablePlugin {
+    fn drop(&mut self) {
+        unsafe {
+            iree_hal_executable_plugin_release(self.plugin_ptr);
+        }
+    }
+}
@@ -0,0 +1,274 @@
+use std::{ffi::c_void, path::Path};
+
+use iree_sys::{
+    helper::IREE_CHECK_OK,
+    iree::{
+        io::api::{
+            iree_io_file_access_bits_t_IREE_IO_FILE_ACCESS_READ, iree_io_file_handle_primitive_t,
+            iree_io_file_handle_release, iree_io_file_handle_release_callback_t,
+            iree_io_file_handle_t, iree_io_file_handle_wrap_host_allocation,
+            iree_io_parameter_index_count, iree_io_parameter_index_create,
+            iree_io_parameter_index_provider_create, iree_io_parameter_index_release,
+            iree_io_parameter_index_t, iree_io_parameter_provider_release,
+            iree_io_parameter_provider_t, iree_io_parameters_module_create,
+            iree_io_parse_file_index,
+            IREE_IO_PARAMETER_INDEX_PROVIDER_DEFAULT_MAX_CONCURRENT_OPERATIONS,
+        },
+        runtime::api::{
+            iree_byte_span_t, iree_runtime_instance_vm_instance, iree_string_view_t,
+            iree_vm_module_t,
+        },
+    },
+};
+
+use crate::err::IreeError;
+
+use super::{
+    allocator::IreeAllocator, runtime::instance::IreeRuntimeInstance, status::IreeStatus,
+    vm_module::IreeVmModule,
+};
+
+fn string_view(s: &str) -> iree_string_view_t {
+    iree_string_view_t {
+        data: s.as_ptr() as _,
+        size: s.len(),
+    }
+}
+
+/// A parameter file mapped read-only into memory (read into memory where mapping isn't available).
+/// Owned by the IREE file handle wrapping it, which unmaps it when released.
+struct HostFile {
+    data: *mut u8,
+    len: usize,
+}
+
+impl HostFile {
+    #[cfg(unix)]
+    fn open(path: &Path) -> std::io::Result<Self> {
+        use std::os::unix::io::AsRawFd;
+
+        let file = std::fs::File::open(path)?;
+        let len = file.metadata()?.len() as usize;
+        if len == 0 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "file is empty",
+            ));
+        }
+        // the mapping stays valid after the file is closed
+        let data = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                len,
+                libc::PROT_READ,
+                libc::MAP_PRIVATE,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+        if data == libc::MAP_FAILED {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok(Self {
+            data: data as *mut u8,
+            len,
+        })
+    }
+
+    #[cfg(not(unix))]
+    fn open(path: &Path) -> std::io::Result<Self> {
+        let data = std::fs::read(path)?.into_boxed_slice();
+        let len = data.len();
+        Ok(Self {
+            data: Box::into_raw(data) as *mut u8,
+            len,
+        })
+    }
+}
+
+impl Drop for HostFile {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        unsafe {
+            libc::munmap(self.data as *mut c_void, self.len);
+        }
+        #[cfg(not(unix))]
+        unsafe {
+            drop(Box::from_raw(std::ptr::slice_from_raw_parts_mut(
+                self.data, self.len,
+            )));
+        }
+    }
+}
+
+unsafe extern "C" fn release_host_file(
+    user_data: *mut c_void,
+    _handle_primitive: iree_io_file_handle_primitive_t,
+) {
+    drop(Box::from_raw(user_data as *mut HostFile));
+}
+
+/// Named parameters (e.g. weights) and where to find them in their files.
+pub struct IreeIoParameterIndex {
+    pub(crate) index_ptr: *mut iree_io_parameter_index_t,
+    allocator: IreeAllocator,
+}
+
+impl IreeIoParameterIndex {
+    pub fn new(instance: &IreeRuntimeInstance) -> Result<Self, IreeError> {
+        let allocator = instance.host_allocator();
+        let mut index_ptr = std::mem::MaybeUninit::<*mut iree_io_parameter_index_t>::uninit();
+        unsafe {
+            let status =
+                iree_io_parameter_index_create(allocator.allocator, index_ptr.as_mut_ptr());
+            if !IREE_CHECK_OK(status) {
+                return Err(IreeError::from_status(IreeStatus { status }, &allocator));
+            }
+            Ok(Self {
+                index_ptr: index_ptr.assume_init(),
+                allocator,
+            })
+        }
+    }
+
+    /// Adds the parameters of an `.irpa`, `.safetensors` or `.gguf` file, chosen by extension. The
+    /// file is mapped into memory and only its index is parsed here, so parameters are used in place
+    /// without copying. The mapping lives as long as the index or a module using it.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, fields(path = %path.as_ref().display()), err)
+    )]
+    pub fn append_file(&mut self, path: impl AsRef<Path>) -> Result<(), IreeError> {
+        let path = path.as_ref();
+        let path_str = path
+            .to_str()
+            .ok_or_else(|| IreeError::from(format!("{:?} is not UTF-8", path)))?;
+        let file = HostFile::open(path)
+            .map_err(|err| IreeError::from(format!("failed to map {}: {}", path.display(), err)))?;
+        let host_allocation = iree_byte_span_t {
+            data: file.data,
+            data_length: file.len,
+        };
+        let file = Box::into_raw(Box::new(file));
+        let mut file_handle = std::mem::MaybeUninit::<*mut iree_io_file_handle_t>::uninit();
+        unsafe {
+            // the handle owns the mapping from here on and unmaps it through the callback
+            let status = iree_io_file_handle_wrap_host_allocation(
+                iree_io_file_access_bits_t_IREE_IO_FILE_ACCESS_READ.0,
+                host_allocation,
+                iree_io_file_handle_release_callback_t {
+                    fn_: Some(release_host_file),
+                    user_data: file as *mut c_void,
+                },
+                self.allocator.allocator,
+                file_handle.as_mut_ptr(),
+            );
+            if !IREE_CHECK_OK(status) {
+                drop(Box::from_raw(file));
+                return Err(IreeError::from_status(
+                    IreeStatus { status },
+                    &self.allocator,
+                ));
+            }
+            let file_handle = file_handle.assume_init();
+            // entries retain the file handle
+            let status = iree_io_parse_file_index(
+                string_view(path_str),
+                file_handle,
+                self.index_ptr,
+                self.allocator.allocator,
+            );
+            iree_io_file_handle_release(file_handle);
+            if !IREE_CHECK_OK(status) {
+                return Err(IreeError::from_status(
+                    IreeStatus { status },
+                    &self.allocator,
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Number of parameters in the index.
+    pub fn len(&self) -> usize {
+        unsafe { iree_io_parameter_index_count(self.index_ptr) }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Drop for IreeIoParameterIndex {
+    fn drop(&mut self) {
+        unsafe {
+            iree_io_parameter_index_release(self.index_ptr);
+        }
+    }
+}
+
+/// Builds the `io_parameters` module that serves parameters to modules compiled with externalized
+/// weights. Append it to a session before the modules using it.
+#[derive(Default)]
+pub struct IreeIoParametersModuleBuilder<'a> {
+    scopes: Vec<(String, &'a IreeIoParameterIndex)>,
+}
+
+impl<'a> IreeIoParametersModuleBuilder<'a> {
+    /// Serves the parameters in `index` under `scope`, e.g. `model` for parameters the compiler
+    /// referenced as `#stream.parameter.named<"model"::"weight">`.
+    pub fn scope(&mut self, scope: &str, index: &'a IreeIoParameterIndex) -> &mut Self {
+        self.scopes.push((scope.to_string(), index));
+        self
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, err))]
+    pub fn build(&self, instance: &IreeRuntimeInstance) -> Result<IreeVmModule, IreeError> {
+        let mut providers = Vec::new();
+        let result = unsafe { self.create(instance, &mut providers) };
+        // the module retains the providers it uses
+        for provider in providers {
+            unsafe {
+                iree_io_parameter_provider_release(provider);
+            }
+        }
+        result
+    }
+
+    unsafe fn create(
+        &self,
+        instance: &IreeRuntimeInstance,
+        providers: &mut Vec<*mut iree_io_parameter_provider_t>,
+    ) -> Result<IreeVmModule, IreeError> {
+        let allocator = instance.host_allocator();
+        for (scope, index) in self.scopes.iter() {
+            let mut provider = std::mem::MaybeUninit::<*mut iree_io_parameter_provider_t>::uninit();
+            let status = iree_io_parameter_index_provider_create(
+                string_view(scope),
+                index.index_ptr,
+                IREE_IO_PARAMETER_INDEX_PROVIDER_DEFAULT_MAX_CONCURRENT_OPERATIONS as _,
+                allocator.allocator,
+                provider.as_mut_ptr(),
+            );
+            if !IREE_CHECK_OK(status) {
+                return Err(IreeError::from_status(IreeStatus { status }, &allocator));
+            }
+            providers.push(provider.assume_init());
+        }
+
+        let mut module_ptr = std::mem::MaybeUninit::<*mut iree_vm_module_t>::uninit();
+        let status = iree_io_parameters_module_create(
+            iree_runtime_instance_vm_instance(instance.instance_ptr),
+            providers.len(),
+            providers.as_ptr(),
+            allocator.allocator,
+            module_ptr.as_mut_ptr(),
+        );
+        if !IREE_CHECK_OK(status) {
+            return Err(IreeError::from_status(IreeStatus { status }, &allocator));
+        }
+        Ok(IreeVmModule {
+            module_ptr: module_ptr.assume_init(),
+        })
+    }
+}
@@ -3,5 +3,14 @@ pub mod bytespan;
 pub mod hal_allocator;
 pub mod hal_buffer;
 pub mod hal_device;
+pub mod hal_element;
+#[cfg(any(feature = "loader-embedded-elf", feature = "loader-system-library"))]
+pub mod hal_executable_plugin;
+#[cfg(feature = "io")]
+pub mod io_parameters;
 pub mod runtime;
 pub mod status;
+pub mod vm_list;
+pub mod vm_module;
+pub mod vm_native_module;
+pub mod vm_value;
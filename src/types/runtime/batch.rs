@@ -0,0 +1,347 @@
+use std::{
+    sync::mpsc,
+    thread::JoinHandle,
+    time::{Duration, Instant},
+};
+
+use iree_sys::iree::runtime::api::{
+    iree_hal_buffer_usage_bits_t_IREE_HAL_BUFFER_USAGE_DEFAULT, iree_hal_element_types_t,
+    iree_hal_encoding_types_t_IREE_HAL_ENCODING_TYPE_DENSE_ROW_MAJOR,
+    iree_hal_memory_type_bits_t_IREE_HAL_MEMORY_TYPE_DEVICE_LOCAL, iree_runtime_call_flags_t,
+};
+
+use crate::{
+    err::{IreeError, IreeErrorKind},
+    types::{
+        bytespan::IreeConstByteSpan,
+        hal_buffer::{IreeHalBufferView, IreeHalBufferViewParamsBuilder},
+        hal_device::IreeHalDevice,
+        hal_element::IreeHalElement,
+    },
+};
+
+use super::{call::IreeRuntimeCall, session::IreeRuntimeSession};
+
+/// One sample of an input or output in host memory, without the batch dimension.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IreeBatchTensor {
+    pub shape: Vec<usize>,
+    pub element_type: iree_hal_element_types_t,
+    pub data: Vec<u8>,
+}
+
+impl IreeBatchTensor {
+    /// A sample of `shape` whose element type is that of `T`.
+    pub fn from_slice<T: IreeHalElement>(shape: &[usize], data: &[T]) -> Self {
+        let bytes = unsafe {
+            std::slice::from_raw_parts(data.as_ptr() as *const u8, std::mem::size_of_val(data))
+        };
+        Self {
+            shape: shape.to_vec(),
+            element_type: T::ELEMENT_TYPE,
+            data: bytes.to_vec(),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct IreeBatchOptions {
+    max_batch_size: usize,
+    timeout: Duration,
+    pad_to_max_batch_size: bool,
+}
+
+pub struct IreeBatchOptionsBuilder {
+    options: IreeBatchOptions,
+}
+
+impl Default for IreeBatchOptionsBuilder {
+    fn default() -> Self {
+        Self {
+            options: IreeBatchOptions {
+                max_batch_size: 8,
+                timeout: Duration::from_millis(5),
+                pad_to_max_batch_size: false,
+            },
+        }
+    }
+}
+
+impl IreeBatchOptionsBuilder {
+    pub fn build(&self) -> IreeBatchOptions {
+        self.options.clone()
+    }
+
+    pub fn max_batch_size(&mut self, max_batch_size: usize) -> &mut Self {
+        self.options.max_batch_size = max_batch_size;
+        self
+    }
+
+    /// How long to wait for more samples after the first one of a batch arrived.
+    pub fn timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.options.timeout = timeout;
+        self
+    }
+
+    /// Pads every batch with zeros up to the maximum batch size, for functions compiled with a
+    /// static batch dimension.
+    pub fn pad_to_max_batch_size(&mut self) -> &mut Self {
+        self.options.pad_to_max_batch_size = true;
+        self
+    }
+}
+
+struct IreeBatchRequest {
+    inputs: Vec<IreeBatchTensor>,
+    response: mpsc::Sender<Result<Vec<IreeBatchTensor>, IreeError>>,
+}
+
+/// Runs a function on a worker thread that owns its session, batching single-sample requests.
+///
+/// Requests are collected until the maximum batch size is reached or the timeout after the first
+/// request expires. Their inputs are concatenated along a new leading batch dimension, the function
+/// is invoked once, and each output is split along its leading dimension back to the callers.
+pub struct IreeBatchWorker {
+    sender: Option<mpsc::Sender<IreeBatchRequest>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl IreeBatchWorker {
+    /// Starts a worker calling `function` (`module.function`) in `session`, which must have been
+    /// created with `device`.
+    pub fn spawn(
+        session: IreeRuntimeSession,
+        device: IreeHalDevice,
+        function: &str,
+        options: &IreeBatchOptions,
+    ) -> Result<Self, IreeError> {
+        if options.max_batch_size == 0 {
+            return Err("the maximum batch size must be at least 1"
+                .to_string()
+                .into());
+        }
+        let (sender, receiver) = mpsc::channel();
+        let (ready_sender, ready_receiver) = mpsc::channel();
+        let function = function.to_string();
+        let options = options.clone();
+        let thread = std::thread::Builder::new()
+            .name(format!("iree-batch-{}", function))
+            .spawn(move || {
+                let call = match session
+                    .lookup_function(&function)
+                    .and_then(|function| IreeRuntimeCall::initialize(&session, &function))
+                {
+                    Ok(call) => call,
+                    Err(err) => {
+                        let _ = ready_sender.send(Err(err));
+                        return;
+                    }
+                };
+                let _ = ready_sender.send(Ok(()));
+                IreeBatcher {
+                    call,
+                    session,
+                    device,
+                    options,
+                }
+                .run(receiver);
+            })
+            .map_err(|err| IreeError::from(format!("failed to spawn batch worker: {}", err)))?;
+
+        match ready_receiver.recv() {
+            Ok(Ok(())) => Ok(Self {
+                sender: Some(sender),
+                thread: Some(thread),
+            }),
+            Ok(Err(err)) => {
+                let _ = thread.join();
+                Err(err)
+            }
+            Err(_) => {
+                let _ = thread.join();
+                Err("batch worker exited during startup".to_string().into())
+            }
+        }
+    }
+
+    /// Queues one sample and waits for its outputs. Can be called from many threads at once. A
+    /// sample whose inputs can't be stacked with the others in its batch fails with
+    /// `IreeErrorKind::InvalidInput`.
+    pub fn infer(&self, inputs: Vec<IreeBatchTensor>) -> Result<Vec<IreeBatchTensor>, IreeError> {
+        let (response, receiver) = mpsc::channel();
+        self.sender
+            .as_ref()
+            .and_then(|sender| sender.send(IreeBatchRequest { inputs, response }).ok())
+            .ok_or_else(|| IreeError::from("batch worker has stopped".to_string()))?;
+        receiver
+            .recv()
+            .map_err(|_| IreeError::from("batch worker has stopped".to_string()))?
+    }
+}
+
+impl Drop for IreeBatchWorker {
+    fn drop(&mut self) {
+        // closing the channel stops the worker once the queued requests are done
+        self.sender.take();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// State of the worker thread. The call is declared first so it is dropped before its session.
+struct IreeBatcher {
+    call: IreeRuntimeCall,
+    session: IreeRuntimeSession,
+    device: IreeHalDevice,
+    options: IreeBatchOptions,
+}
+
+impl IreeBatcher {
+    fn run(&mut self, receiver: mpsc::Receiver<IreeBatchRequest>) {
+        while let Ok(first) = receiver.recv() {
+            let deadline = Instant::now() + self.options.timeout;
+            let mut requests = vec![first];
+            while requests.len() < self.options.max_batch_size {
+                match receiver.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                    Ok(request) => requests.push(request),
+                    Err(_) => break,
+                }
+            }
+            self.process(requests);
+        }
+    }
+
+    fn process(&mut self, requests: Vec<IreeBatchRequest>) {
+        // samples that can't be stacked with the first one are rejected on their own
+        let expected = layout(&requests[0].inputs);
+        let (requests, rejected): (Vec<_>, Vec<_>) = requests
+            .into_iter()
+            .partition(|request| layout(&request.inputs) == expected);
+        for request in rejected {
+            let _ = request
+                .response
+                .send(Err(IreeError::new(IreeErrorKind::InvalidInput(
+                    self.call.function_name(),
+                    format!(
+                        "inputs {} don't match the other samples in the batch ({})",
+                        describe(&layout(&request.inputs)),
+                        describe(&expected)
+                    ),
+                ))));
+        }
+
+        let samples = requests
+            .iter()
+            .map(|request| request.inputs.as_slice())
+            .collect::<Vec<_>>();
+        let result = self.invoke(&samples);
+        self.call.reset();
+        match result {
+            Ok(outputs) => {
+                for (request, outputs) in requests.into_iter().zip(outputs) {
+                    let _ = request.response.send(Ok(outputs));
+                }
+            }
+            Err(err) => {
+                for request in requests {
+                    let _ = request.response.send(Err(copy_error(&err)));
+                }
+            }
+        }
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, fields(samples = samples.len()), err)
+    )]
+    fn invoke(
+        &mut self,
+        samples: &[&[IreeBatchTensor]],
+    ) -> Result<Vec<Vec<IreeBatchTensor>>, IreeError> {
+        let batch_size = if self.options.pad_to_max_batch_size {
+            self.options.max_batch_size
+        } else {
+            samples.len()
+        };
+        let allocator = self.session.device_allocator();
+        let params = IreeHalBufferViewParamsBuilder::default()
+            .type_(iree_hal_memory_type_bits_t_IREE_HAL_MEMORY_TYPE_DEVICE_LOCAL.0)
+            .usage(iree_hal_buffer_usage_bits_t_IREE_HAL_BUFFER_USAGE_DEFAULT.0)
+            .build();
+
+        for (i, input) in samples[0].iter().enumerate() {
+            let mut data = Vec::with_capacity(input.data.len() * batch_size);
+            for sample in samples {
+                data.extend_from_slice(&sample[i].data);
+            }
+            data.resize(input.data.len() * batch_size, 0);
+            let mut shape = vec![batch_size];
+            shape.extend_from_slice(&input.shape);
+            let buffer_view = IreeHalBufferView::allocate_buffer_copy(
+                &self.device,
+                &allocator,
+                &shape,
+                input.element_type,
+                iree_hal_encoding_types_t_IREE_HAL_ENCODING_TYPE_DENSE_ROW_MAJOR,
+                &params,
+                &IreeConstByteSpan::from_slice(&data),
+            )?;
+            self.call.inputs_push_back_buffer_view(&buffer_view)?;
+        }
+        self.call.invoke(iree_runtime_call_flags_t::default())?;
+
+        let mut outputs = vec![Vec::new(); samples.len()];
+        while self.call.outputs_len() > 0 {
+            let output = self.call.outputs_pop_front_buffer_view()?;
+            let shape = output.shape()?;
+            if shape.first() != Some(&batch_size) {
+                return Err(format!(
+                    "output of shape {:?} has no leading batch dimension of {}",
+                    shape, batch_size
+                )
+                .into());
+            }
+            let data = output.read_bytes(&self.device)?;
+            let sample_length = data.len() / batch_size;
+            for (j, sample_outputs) in outputs.iter_mut().enumerate() {
+                sample_outputs.push(IreeBatchTensor {
+                    shape: shape[1..].to_vec(),
+                    element_type: output.element_type(),
+                    data: data[j * sample_length..(j + 1) * sample_length].to_vec(),
+                });
+            }
+        }
+        Ok(outputs)
+    }
+}
+
+/// A copy of the error of a batch for each of its requests. Errors of other kinds than these can't
+/// be cloned and are passed on as their message.
+fn copy_error(err: &IreeError) -> IreeError {
+    let kind = match err.kind() {
+        IreeErrorKind::Status(status, msg) => IreeErrorKind::Status(*status, msg.clone()),
+        IreeErrorKind::UnallocatedStatus(status) => IreeErrorKind::UnallocatedStatus(*status),
+        IreeErrorKind::InvalidInput(function, msg) => {
+            IreeErrorKind::InvalidInput(function.clone(), msg.clone())
+        }
+        _ => IreeErrorKind::Unknown(err.to_string()),
+    };
+    IreeError::new(kind)
+}
+
+/// Shape, element type and byte length of each input of a sample.
+fn layout(inputs: &[IreeBatchTensor]) -> Vec<(Vec<usize>, iree_hal_element_types_t, usize)> {
+    inputs
+        .iter()
+        .map(|input| (input.shape.clone(), input.element_type, input.data.len()))
+        .collect()
+}
+
+fn describe(layout: &[(Vec<usize>, iree_hal_element_types_t, usize)]) -> String {
+    let inputs = layout
+        .iter()
+        .map(|(shape, element_type, _)| format!("{:?} of type {:#x}", shape, element_type.0))
+        .collect::<Vec<_>>();
+    format!("[{}]", inputs.join(", "))
+}
@@ -2,38 +2,132 @@ use iree_sys::{
     helper::IREE_CHECK_OK,
     iree::runtime::api::{
         iree_hal_buffer_view_t, iree_runtime_call_deinitialize, iree_runtime_call_flags_t,
-        iree_runtime_call_initialize_by_name, iree_runtime_call_inputs_push_back_buffer_view,
-        iree_runtime_call_invoke, iree_runtime_call_outputs_pop_front_buffer_view,
-        iree_runtime_call_t, iree_string_view_t,
+        iree_runtime_call_initialize, iree_runtime_call_inputs,
+        iree_runtime_call_inputs_push_back_buffer_view, iree_runtime_call_invoke,
+        iree_runtime_call_outputs, iree_runtime_call_outputs_pop_front_buffer_view,
+        iree_runtime_call_reset, iree_runtime_call_t, iree_string_view_t,
+        iree_vm_function_lookup_attr_by_name, iree_vm_function_name, iree_vm_function_signature,
+        iree_vm_function_t, iree_vm_list_clear, iree_vm_list_size, iree_vm_module_name,
     },
 };
 
 use crate::{
-    err::IreeError,
-    types::{allocator::IreeAllocator, hal_buffer::IreeHalBufferView, status::IreeStatus},
+    err::{IreeError, IreeErrorKind},
+    types::{
+        allocator::IreeAllocator,
+        hal_buffer::IreeHalBufferView,
+        status::IreeStatus,
+        vm_list::list_get,
+        vm_value::{IreeVmValue, IreeVmValueType},
+    },
 };
 
-use super::session::IreeRuntimeSession;
+use super::{session::IreeRuntimeSession, signature::IreeFunctionSignature};
+
+/// Copies a string view, which may be empty with a null `data`.
+pub(crate) fn view_to_string(view: iree_string_view_t) -> String {
+    if view.data.is_null() {
+        return String::new();
+    }
+    unsafe {
+        String::from_utf8_lossy(std::slice::from_raw_parts(
+            view.data as *const u8,
+            view.size,
+        ))
+        .to_string()
+    }
+}
+
+/// A function resolved once with `IreeRuntimeSession::lookup_function`, to initialize calls without
+/// looking it up by name or parsing its signature again. Borrows the session it was looked up in,
+/// whose modules it points into, and only initializes calls in that session.
+#[derive(Clone)]
+pub struct IreeRuntimeFunction<'s> {
+    pub(crate) function: iree_vm_function_t,
+    signature: Option<IreeFunctionSignature>,
+    session: &'s IreeRuntimeSession,
+}
+
+impl<'s> IreeRuntimeFunction<'s> {
+    pub(crate) fn new(session: &'s IreeRuntimeSession, function: iree_vm_function_t) -> Self {
+        Self {
+            function,
+            signature: parse_signature(&function),
+            session,
+        }
+    }
+
+    /// Returns the fully qualified name (`module.function`) of the function.
+    pub fn name(&self) -> String {
+        unsafe {
+            let module_name = iree_vm_module_name(self.function.module);
+            let function_name = iree_vm_function_name(&self.function);
+            format!(
+                "{}.{}",
+                view_to_string(module_name),
+                view_to_string(function_name)
+            )
+        }
+    }
+
+    /// Returns the arguments and results the function expects, or `None` if its calling convention
+    /// can't be checked (e.g. variadic arguments).
+    pub fn signature(&self) -> Option<&IreeFunctionSignature> {
+        self.signature.as_ref()
+    }
+}
+
+fn parse_signature(function: &iree_vm_function_t) -> Option<IreeFunctionSignature> {
+    let cconv = view_to_string(unsafe { iree_vm_function_signature(function) }.calling_convention);
+    let attrs = ["iree.abi.declaration", "iree.abi"]
+        .iter()
+        .map(|key| {
+            let value = unsafe {
+                iree_vm_function_lookup_attr_by_name(
+                    function,
+                    iree_string_view_t {
+                        data: key.as_ptr() as *const i8,
+                        size: key.len(),
+                    },
+                )
+            };
+            (*key, view_to_string(value))
+        })
+        .filter(|(_, value)| !value.is_empty())
+        .collect::<Vec<_>>();
+    let attrs = attrs
+        .iter()
+        .map(|(key, value)| (*key, value.as_str()))
+        .collect::<Vec<_>>();
+    IreeFunctionSignature::parse(&cconv, &attrs)
+}
 
 pub struct IreeRuntimeCall {
     pub(crate) call: iree_runtime_call_t,
+    signature: Option<IreeFunctionSignature>,
 }
 impl IreeRuntimeCall {
-    pub fn initialize_by_name(
+    fn invalid_input(&self, msg: String) -> IreeError {
+        IreeError::new(IreeErrorKind::InvalidInput(self.function_name(), msg))
+    }
+
+    /// Initializes a call to a function looked up before, skipping the lookup by name. Fails with
+    /// `IreeErrorKind::InvalidInput` if the function was looked up in another session, whose
+    /// module state this session doesn't share.
+    pub fn initialize(
         session: &IreeRuntimeSession,
-        full_name: &String,
+        function: &IreeRuntimeFunction<'_>,
     ) -> Result<Self, IreeError> {
+        if function.session.session_ptr != session.session_ptr {
+            return Err(IreeError::new(IreeErrorKind::InvalidInput(
+                function.name(),
+                "the function was looked up in another session".to_string(),
+            )));
+        }
         let mut call = iree_runtime_call_t::default();
-
         unsafe {
-            let status = iree_runtime_call_initialize_by_name(
-                session.session_ptr,
-                iree_string_view_t {
-                    data: full_name.as_ptr() as *const i8,
-                    size: full_name.len(),
-                },
-                &mut call,
-            );
+            let status =
+                iree_runtime_call_initialize(session.session_ptr, function.function, &mut call);
             if !IREE_CHECK_OK(status) {
                 return Err(IreeError::from_status(
                     IreeStatus { status },
@@ -41,14 +135,34 @@ impl IreeRuntimeCall {
                 ));
             }
         }
+        Ok(Self {
+            call,
+            signature: function.signature.clone(),
+        })
+    }
 
-        Ok(Self { call })
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, fields(function = %full_name), err)
+    )]
+    pub fn initialize_by_name(
+        session: &IreeRuntimeSession,
+        full_name: &String,
+    ) -> Result<Self, IreeError> {
+        Self::initialize(session, &session.lookup_function(full_name)?)
     }
 
+    /// Appends an input, checking its element type and shape against the function signature first.
     pub fn inputs_push_back_buffer_view(
         &mut self,
         buffer_view: &IreeHalBufferView,
     ) -> Result<(), IreeError> {
+        if let Some(signature) = &self.signature {
+            let index = unsafe { iree_vm_list_size(iree_runtime_call_inputs(&self.call)) };
+            signature
+                .check_buffer_view(index, buffer_view)
+                .map_err(|msg| self.invalid_input(msg))?;
+        }
         unsafe {
             let status = iree_runtime_call_inputs_push_back_buffer_view(
                 &mut self.call,
@@ -83,7 +197,119 @@ impl IreeRuntimeCall {
         }
     }
 
+    /// Number of outputs left after the last invocation.
+    pub fn outputs_len(&self) -> usize {
+        unsafe { iree_vm_list_size(iree_runtime_call_outputs(&self.call)) }
+    }
+
+    /// Returns output `i` of the outputs left, typed by the function signature. Buffer views and
+    /// lists are retained, so the output stays in the call until it is reset.
+    pub fn output(&self, i: usize) -> Result<IreeVmValue, IreeError> {
+        let len = self.outputs_len();
+        if i >= len {
+            return Err(format!("output {} out of range, the call has {} outputs", i, len).into());
+        }
+        let value_type = self.output_types(len).map(|types| types[i]);
+        unsafe { list_get(iree_runtime_call_outputs(&self.call), i, value_type) }
+    }
+
+    /// Removes and returns all outputs left.
+    pub fn take_outputs(&mut self) -> Result<Vec<IreeVmValue>, IreeError> {
+        let outputs = (0..self.outputs_len())
+            .map(|i| self.output(i))
+            .collect::<Result<Vec<_>, _>>()?;
+        unsafe {
+            iree_vm_list_clear(iree_runtime_call_outputs(&self.call));
+        }
+        Ok(outputs)
+    }
+
+    /// Types of the last `len` results from the calling convention, or `None` if it can't be parsed
+    /// (e.g. for variadic results).
+    fn output_types(&self, len: usize) -> Option<Vec<IreeVmValueType>> {
+        let signature = unsafe { iree_vm_function_signature(&self.call.function) };
+        let cconv = signature.calling_convention;
+        if cconv.data.is_null() {
+            return None;
+        }
+        let cconv = unsafe { std::slice::from_raw_parts(cconv.data as *const u8, cconv.size) };
+        let (_, results) = std::str::from_utf8(cconv).ok()?.split_once('_')?;
+        let types = match results {
+            "v" => Vec::new(),
+            results => results
+                .chars()
+                .map(IreeVmValueType::from_cconv_char)
+                .collect::<Option<Vec<_>>>()?,
+        };
+        // outputs popped before are gone from the front
+        types
+            .len()
+            .checked_sub(len)
+            .map(|popped| types[popped..].to_vec())
+    }
+
+    /// Returns the fully qualified name (`module.function`) of the function being called.
+    pub fn function_name(&self) -> String {
+        self.function().name()
+    }
+
+    /// The function being called, e.g. to initialize more calls to it. The call keeps its session
+    /// alive, so the function borrows the call.
+    pub fn function(&self) -> IreeRuntimeFunction<'_> {
+        IreeRuntimeFunction {
+            function: self.call.function,
+            signature: self.signature.clone(),
+            session: PhantomData,
+        }
+    }
+
+    /// Shapes of the inputs pushed so far (e.g. `2x4xf32`), or their types for scalars and lists.
+    #[cfg(feature = "tracing")]
+    fn input_shapes(&self) -> Vec<String> {
+        let inputs = unsafe { iree_runtime_call_inputs(&self.call) };
+        (0..unsafe { iree_vm_list_size(inputs) })
+            .map(|i| match unsafe { list_get(inputs, i, None) } {
+                Ok(IreeVmValue::BufferView(view)) => {
+                    let mut shape = view
+                        .shape()
+                        .unwrap_or_default()
+                        .iter()
+                        .map(|dim| format!("{}x", dim))
+                        .collect::<String>();
+                    shape.push_str(&crate::types::hal_element::element_type_name(
+                        view.element_type(),
+                    ));
+                    shape
+                }
+                Ok(value) => format!("{:?}", value.value_type()),
+                Err(_) => "?".to_string(),
+            })
+            .collect()
+    }
+
+    /// Drops all inputs and outputs so the call can be invoked again with new inputs. The function
+    /// and the storage of the argument lists are kept.
+    pub fn reset(&mut self) {
+        unsafe {
+            iree_runtime_call_reset(&mut self.call);
+        }
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            skip_all,
+            fields(function = %self.function_name(), shapes = ?self.input_shapes()),
+            err
+        )
+    )]
     pub fn invoke(&mut self, flags: iree_runtime_call_flags_t) -> Result<(), IreeError> {
+        if let Some(signature) = &self.signature {
+            let count = unsafe { iree_vm_list_size(iree_runtime_call_inputs(&self.call)) };
+            signature
+                .check_input_count(count)
+                .map_err(|msg| self.invalid_input(msg))?;
+        }
         unsafe {
             let status = iree_runtime_call_invoke(&mut self.call, flags);
             if !IREE_CHECK_OK(status) {
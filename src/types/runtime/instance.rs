@@ -1,7 +1,9 @@
 use iree_sys::{
     helper::IREE_CHECK_OK,
     iree::runtime::api::{
-        iree_hal_device_t, iree_runtime_instance_create, iree_runtime_instance_host_allocator,
+        iree_allocator_free, iree_hal_device_t, iree_hal_driver_info_t,
+        iree_hal_driver_registry_enumerate, iree_runtime_instance_create,
+        iree_runtime_instance_driver_registry, iree_runtime_instance_host_allocator,
         iree_runtime_instance_options_initialize, iree_runtime_instance_options_t,
         iree_runtime_instance_options_use_all_available_drivers, iree_runtime_instance_release,
         iree_runtime_instance_t, iree_runtime_instance_try_create_default_device,
@@ -14,6 +16,8 @@ use crate::{
     types::{allocator::IreeAllocator, hal_device::IreeHalDevice, status::IreeStatus},
 };
 
+use super::call::view_to_string;
+
 pub struct IreeRuntimeInstanceOptions {
     options: iree_runtime_instance_options_t,
 }
@@ -33,12 +37,18 @@ impl Default for IreeRuntimeInstanceOptionsBuilder {
 }
 
 impl IreeRuntimeInstanceOptionsBuilder {
+    /// Registers all HAL drivers compiled into the runtime, see `available_drivers`.
     pub fn use_all_available_drivers(&mut self) -> &mut Self {
         unsafe {
             iree_runtime_instance_options_use_all_available_drivers(&mut self.options);
         }
         self
     }
+    /// Names of the HAL drivers compiled into the runtime, as selected by the `driver-*` features.
+    pub fn available_drivers() -> &'static [&'static str] {
+        iree_sys::HAL_DRIVERS
+    }
+
     pub fn build(&self) -> IreeRuntimeInstanceOptions {
         IreeRuntimeInstanceOptions {
             options: self.options,
@@ -51,10 +61,14 @@ pub struct IreeRuntimeInstance {
 }
 
 impl IreeRuntimeInstance {
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, err))]
     pub fn try_from_options(
         options: &IreeRuntimeInstanceOptions,
         allocator: &IreeAllocator,
     ) -> Result<Self, IreeError> {
+        // report a missing runtime library here rather than panicking in the first call
+        #[cfg(feature = "dynamic")]
+        iree_sys::dynamic::ensure_loaded()?;
         let mut instance_ptr = std::mem::MaybeUninit::<*mut iree_runtime_instance_t>::uninit();
         unsafe {
             let status = iree_runtime_instance_create(
@@ -76,7 +90,44 @@ impl IreeRuntimeInstance {
         IreeAllocator { allocator }
     }
 
+    /// Names of the drivers registered with the instance.
+    pub fn driver_names(&self) -> Result<Vec<String>, IreeError> {
+        let allocator = self.host_allocator();
+        let mut count = 0;
+        let mut infos = std::ptr::null_mut::<iree_hal_driver_info_t>();
+        unsafe {
+            let status = iree_hal_driver_registry_enumerate(
+                iree_runtime_instance_driver_registry(self.instance_ptr),
+                allocator.allocator,
+                &mut count,
+                &mut infos,
+            );
+            if !IREE_CHECK_OK(status) {
+                return Err(IreeError::from_status(IreeStatus { status }, &allocator));
+            }
+            if infos.is_null() {
+                return Ok(Vec::new());
+            }
+            let names = std::slice::from_raw_parts(infos, count)
+                .iter()
+                .map(|info| view_to_string(info.driver_name))
+                .collect();
+            iree_allocator_free(allocator.allocator, infos as _);
+            Ok(names)
+        }
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, fields(driver = driver_name), err)
+    )]
     pub fn try_create_default_device(&self, driver_name: &str) -> Result<IreeHalDevice, IreeError> {
+        // the registry creates the driver with exactly this name, so report its own copy
+        let driver = self
+            .driver_names()?
+            .into_iter()
+            .find(|name| name == driver_name)
+            .ok_or_else(|| format!("no HAL driver named '{}' is registered", driver_name))?;
         let driver_name = iree_string_view_t {
             data: driver_name.as_ptr() as _,
             size: driver_name.len() as _,
@@ -97,6 +148,7 @@ impl IreeRuntimeInstance {
         }
         Ok(IreeHalDevice {
             device_ptr: unsafe { device_ptr.assume_init() },
+            driver,
         })
     }
 }
@@ -1,3 +1,5 @@
+pub mod batch;
 pub mod call;
 pub mod instance;
 pub mod session;
+pub mod signature;
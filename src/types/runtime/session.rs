@@ -1,11 +1,12 @@
 use iree_sys::{
     helper::IREE_CHECK_OK,
     iree::runtime::api::{
-        iree_const_byte_span_t, iree_runtime_call_initialize_by_name, iree_runtime_call_t,
-        iree_runtime_session_append_bytecode_module_from_memory,
-        iree_runtime_session_create_with_device, iree_runtime_session_device_allocator,
+        iree_const_byte_span_t, iree_runtime_session_append_bytecode_module_from_memory,
+        iree_runtime_session_append_module, iree_runtime_session_create_with_device,
+        iree_runtime_session_device_allocator, iree_runtime_session_lookup_function,
         iree_runtime_session_options_initialize, iree_runtime_session_options_t,
-        iree_runtime_session_release, iree_runtime_session_t, iree_string_view_t,
+        iree_runtime_session_release, iree_runtime_session_t, iree_runtime_session_trim,
+        iree_string_view_t, iree_vm_function_t,
     },
 };
 
@@ -13,11 +14,15 @@ use crate::{
     err::IreeError,
     types::{
         allocator::IreeAllocator, hal_allocator::IreeHalAllocator, hal_device::IreeHalDevice,
-        status::IreeStatus,
+        status::IreeStatus, vm_module::IreeVmModule,
     },
+    vmfb::variants::IreeVmfbVariants,
 };
 
-use super::{call::IreeRuntimeCall, instance::IreeRuntimeInstance};
+use super::{
+    call::{IreeRuntimeCall, IreeRuntimeFunction},
+    instance::IreeRuntimeInstance,
+};
 
 pub struct IreeRuntimeSessionOptions {
     options: iree_runtime_session_options_t,
@@ -50,6 +55,7 @@ pub struct IreeRuntimeSession {
 }
 
 impl IreeRuntimeSession {
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, err))]
     pub fn create_with_device(
         instance: &IreeRuntimeInstance,
         options: &IreeRuntimeSessionOptions,
@@ -84,29 +90,58 @@ impl IreeRuntimeSession {
         IreeHalAllocator { allocator_ptr }
     }
 
+    /// Releases transient and cached resources held by the session and its device, e.g. when idle
+    /// or under memory pressure. Buffers still referenced by the caller are not affected.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, err))]
+    pub fn trim(&self) -> Result<(), IreeError> {
+        unsafe {
+            let status = iree_runtime_session_trim(self.session_ptr);
+            if !IREE_CHECK_OK(status) {
+                return Err(IreeError::from_status(
+                    IreeStatus { status },
+                    &IreeAllocator::system_allocator(),
+                ));
+            }
+        }
+        Ok(())
+    }
+
     pub fn get_call_by_name(&self, full_name: &str) -> Result<IreeRuntimeCall, IreeError> {
-        let mut call = iree_runtime_call_t::default();
+        IreeRuntimeCall::initialize(self, &self.lookup_function(full_name)?)
+    }
+
+    /// Resolves a function by its fully qualified name (`module.function`) and parses its signature
+    /// once, so calls to it can be initialized with `IreeRuntimeCall::initialize` without another
+    /// lookup.
+    pub fn lookup_function(&self, full_name: &str) -> Result<IreeRuntimeFunction<'_>, IreeError> {
+        let mut function = iree_vm_function_t::default();
         unsafe {
-            let status = iree_runtime_call_initialize_by_name(
+            let status = iree_runtime_session_lookup_function(
                 self.session_ptr,
                 iree_string_view_t {
                     data: full_name.as_ptr() as *const i8,
                     size: full_name.len(),
                 },
-                &mut call,
+                &mut function,
             );
-
             if !IREE_CHECK_OK(status) {
                 return Err(IreeError::from_status(
                     IreeStatus { status },
                     &IreeAllocator::system_allocator(),
                 ));
             }
-
-            Ok(IreeRuntimeCall { call })
         }
+        Ok(IreeRuntimeFunction::new(self, function))
     }
 
+    /// Loads and appends a bytecode module. `allocator` frees `module_data` when the module is
+    /// released, so pass `IreeAllocator::null()` for data owned by Rust, which must then outlive the
+    /// session. To append the same module to several sessions, load it once with
+    /// `IreeVmModule::from_bytecode` instead.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, fields(bytes = module_data.len()), err)
+    )]
     pub fn append_bytecode_module_from_memory(
         &self,
         module_data: &[u8],
@@ -123,13 +158,46 @@ impl IreeRuntimeSession {
                 allocator.allocator,
             );
             if !IREE_CHECK_OK(status) {
-                return Err(IreeError::from_status(IreeStatus { status }, allocator));
+                return Err(IreeError::from_status(
+                    IreeStatus { status },
+                    &IreeAllocator::system_allocator(),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Appends the best of `variants` this host can run and returns its label. The module data is
+    /// borrowed from `variants`, which must outlive the session.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, err))]
+    pub fn append_bytecode_module_variant(
+        &self,
+        variants: &IreeVmfbVariants,
+    ) -> Result<String, IreeError> {
+        let variant = variants.select_for_host()?;
+        self.append_bytecode_module_from_memory(variant.data, &IreeAllocator::null())?;
+        Ok(variant.label.clone())
+    }
+
+    /// Appends a module to the session. Modules must be appended before any module importing them.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, err))]
+    pub fn append_module(&self, module: &IreeVmModule) -> Result<(), IreeError> {
+        unsafe {
+            let status = iree_runtime_session_append_module(self.session_ptr, module.module_ptr);
+            if !IREE_CHECK_OK(status) {
+                return Err(IreeError::from_status(
+                    IreeStatus { status },
+                    &IreeAllocator::system_allocator(),
+                ));
             }
         }
         Ok(())
     }
 }
 
+// sessions may be moved to another thread, but not used from several threads at once
+unsafe impl Send for IreeRuntimeSession {}
+
 impl Drop for IreeRuntimeSession {
     fn drop(&mut self) {
         unsafe {
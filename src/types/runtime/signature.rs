@@ -0,0 +1,294 @@
+use std::fmt::Display;
+
+use serde_json::Value;
+
+use crate::types::{
+    hal_buffer::IreeHalBufferView, hal_element::element_type_name, vm_value::IreeVmValueType,
+};
+
+/// What a function expects for one argument or result.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum IreeArgumentType {
+    /// A buffer view with this element type (e.g. `f32`) and shape, `None` for dynamic dims.
+    Tensor {
+        dtype: String,
+        shape: Vec<Option<usize>>,
+    },
+    Scalar(IreeVmValueType),
+    /// Any other reference, e.g. a list or a buffer view without reflection metadata.
+    Ref,
+}
+
+impl Display for IreeArgumentType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IreeArgumentType::Tensor { dtype, shape } => {
+                write!(f, "tensor<")?;
+                for dim in shape.iter() {
+                    match dim {
+                        Some(dim) => write!(f, "{}x", dim)?,
+                        None => write!(f, "?x")?,
+                    }
+                }
+                write!(f, "{}>", dtype)
+            }
+            IreeArgumentType::Scalar(value_type) => write!(f, "{}", scalar_name(*value_type)),
+            IreeArgumentType::Ref => write!(f, "ref"),
+        }
+    }
+}
+
+fn scalar_name(value_type: IreeVmValueType) -> &'static str {
+    match value_type {
+        IreeVmValueType::I32 => "i32",
+        IreeVmValueType::I64 => "i64",
+        IreeVmValueType::F32 => "f32",
+        IreeVmValueType::F64 => "f64",
+        IreeVmValueType::Ref => "ref",
+    }
+}
+
+/// Arguments and results of a function, from its calling convention and, where the compiler
+/// recorded them, the tensor types in its reflection attributes (`iree.abi.declaration` or the
+/// older `iree.abi`).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IreeFunctionSignature {
+    pub arguments: Vec<IreeArgumentType>,
+    pub results: Vec<IreeArgumentType>,
+}
+
+impl IreeFunctionSignature {
+    /// Returns `None` for calling conventions with variadic segments, which aren't checked.
+    pub fn parse(calling_convention: &str, attrs: &[(&str, &str)]) -> Option<Self> {
+        let (arguments, results) = calling_convention.strip_prefix('0')?.split_once('_')?;
+        let mut signature = Self {
+            arguments: cconv_types(arguments)?,
+            results: cconv_types(results)?,
+        };
+
+        let attr = |key: &str| attrs.iter().find(|(k, _)| *k == key).map(|(_, v)| *v);
+        let reflected = match (attr("iree.abi.declaration"), attr("iree.abi")) {
+            (Some(declaration), _) => parse_declaration(declaration),
+            (None, Some(abi)) => parse_abi_json(abi),
+            (None, None) => None,
+        };
+        if let Some((arguments, results)) = reflected {
+            refine(&mut signature.arguments, arguments);
+            refine(&mut signature.results, results);
+        }
+        Some(signature)
+    }
+
+    /// Checks that `count` inputs match the number of arguments.
+    pub fn check_input_count(&self, count: usize) -> Result<(), String> {
+        if count != self.arguments.len() {
+            return Err(format!(
+                "expected {} arguments ({}), got {}",
+                self.arguments.len(),
+                self.arguments
+                    .iter()
+                    .map(|argument| argument.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                count
+            ));
+        }
+        Ok(())
+    }
+
+    /// Checks a buffer view passed as argument `index`.
+    pub fn check_buffer_view(
+        &self,
+        index: usize,
+        buffer_view: &IreeHalBufferView,
+    ) -> Result<(), String> {
+        let expected = self.arguments.get(index).ok_or_else(|| {
+            format!(
+                "argument {}: the function only takes {} arguments",
+                index,
+                self.arguments.len()
+            )
+        })?;
+        let (dtype, shape) = match expected {
+            IreeArgumentType::Tensor { dtype, shape } => (dtype, shape),
+            IreeArgumentType::Ref => return Ok(()),
+            IreeArgumentType::Scalar(_) => {
+                return Err(format!(
+                    "argument {}: expected {}, got a buffer view",
+                    index, expected
+                ))
+            }
+        };
+
+        let actual_shape = buffer_view.shape().map_err(|err| err.to_string())?;
+        let actual_dtype = element_type_name(buffer_view.element_type());
+        let shape_matches = shape.len() == actual_shape.len()
+            && shape
+                .iter()
+                .zip(actual_shape.iter())
+                .all(|(expected, actual)| expected.is_none_or(|dim| dim == *actual));
+        if !shape_matches || !dtype_matches(dtype, &actual_dtype) {
+            let actual = IreeArgumentType::Tensor {
+                dtype: actual_dtype,
+                shape: actual_shape.into_iter().map(Some).collect(),
+            };
+            return Err(format!(
+                "argument {}: expected {}, got {}",
+                index, expected, actual
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn cconv_types(fragment: &str) -> Option<Vec<IreeArgumentType>> {
+    if fragment == "v" {
+        return Some(Vec::new());
+    }
+    fragment
+        .chars()
+        .map(|c| match IreeVmValueType::from_cconv_char(c)? {
+            IreeVmValueType::Ref => Some(IreeArgumentType::Ref),
+            value_type => Some(IreeArgumentType::Scalar(value_type)),
+        })
+        .collect()
+}
+
+/// Replaces untyped references with the reflected tensor types, if the counts agree.
+fn refine(types: &mut [IreeArgumentType], reflected: Vec<IreeArgumentType>) {
+    if types.len() != reflected.len() {
+        return;
+    }
+    for (argument, reflected) in types.iter_mut().zip(reflected) {
+        if *argument == IreeArgumentType::Ref
+            && matches!(reflected, IreeArgumentType::Tensor { .. })
+        {
+            *argument = reflected;
+        }
+    }
+}
+
+/// Integer types match regardless of signedness, since MLIR integers are signless.
+fn dtype_matches(expected: &str, actual: &str) -> bool {
+    let integer_bits = |dtype: &str| {
+        dtype
+            .strip_prefix("si")
+            .or_else(|| dtype.strip_prefix("ui"))
+            .or_else(|| dtype.strip_prefix('i'))
+            .and_then(|bits| bits.parse::<u32>().ok())
+    };
+    expected == actual
+        || matches!(
+            (integer_bits(expected), integer_bits(actual)),
+            (Some(expected), Some(actual)) if expected == actual
+        )
+}
+
+fn parse_type(ty: &str) -> IreeArgumentType {
+    let ty = ty.trim();
+    if let Some(tensor) = ty.strip_prefix("tensor<").and_then(|t| t.strip_suffix('>')) {
+        // drop an encoding, e.g. `tensor<4xf32, #encoding>`
+        let tensor = tensor.split(',').next().unwrap_or_default();
+        let mut parts = tensor.split('x').collect::<Vec<_>>();
+        let dtype = parts.pop().unwrap_or_default().trim().to_string();
+        let shape = parts.iter().map(|dim| dim.trim().parse().ok()).collect();
+        return IreeArgumentType::Tensor { dtype, shape };
+    }
+    match ty {
+        "i32" => IreeArgumentType::Scalar(IreeVmValueType::I32),
+        "i64" => IreeArgumentType::Scalar(IreeVmValueType::I64),
+        "f32" => IreeArgumentType::Scalar(IreeVmValueType::F32),
+        "f64" => IreeArgumentType::Scalar(IreeVmValueType::F64),
+        _ => IreeArgumentType::Ref,
+    }
+}
+
+/// Splits at commas that aren't nested in brackets.
+fn split_top_level(s: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0i32;
+    let mut start = 0;
+    for (i, c) in s.char_indices() {
+        match c {
+            '<' | '(' | '[' | '{' => depth += 1,
+            '>' | ')' | ']' | '}' => depth -= 1,
+            ',' if depth == 0 => {
+                parts.push(&s[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    if !s[start..].trim().is_empty() {
+        parts.push(&s[start..]);
+    }
+    parts
+}
+
+/// Splits a parenthesized list starting at the beginning of `s`, returning its items and the rest.
+fn parenthesized(s: &str) -> Option<(Vec<IreeArgumentType>, &str)> {
+    let s = s.trim_start().strip_prefix('(')?;
+    let mut depth = 1;
+    let end = s.char_indices().find_map(|(i, c)| {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            _ => {}
+        }
+        (depth == 0).then_some(i)
+    })?;
+    let types = split_top_level(&s[..end])
+        .into_iter()
+        .map(|item| {
+            // `%input0: tensor<4xf32> {attrs}`
+            let item = item.split_once(':').map_or(item, |(_, ty)| ty);
+            parse_type(item.split('{').next().unwrap_or_default())
+        })
+        .collect();
+    Some((types, &s[end + 1..]))
+}
+
+/// Parses e.g. `sync func @main(%input0: tensor<4xf32>) -> (%output0: tensor<4xf32>)`.
+fn parse_declaration(declaration: &str) -> Option<(Vec<IreeArgumentType>, Vec<IreeArgumentType>)> {
+    let (_, rest) = declaration.split_once('@')?;
+    let (arguments, rest) = parenthesized(&rest[rest.find('(')?..])?;
+    let results = match rest.trim_start().strip_prefix("->") {
+        Some(results) if results.trim_start().starts_with('(') => parenthesized(results)?.0,
+        Some(result) => vec![parse_type(result)],
+        None => Vec::new(),
+    };
+    Some((arguments, results))
+}
+
+/// Parses e.g. `{"a":[["ndarray","f32",1,4]],"r":[["ndarray","f32",1,4]],"v":1}`.
+fn parse_abi_json(abi: &str) -> Option<(Vec<IreeArgumentType>, Vec<IreeArgumentType>)> {
+    let abi = serde_json::from_str::<Value>(abi).ok()?;
+    let list = |key: &str| -> Vec<IreeArgumentType> {
+        let items = match abi.get(key) {
+            Some(Value::Array(items)) => items,
+            _ => return Vec::new(),
+        };
+        items
+            .iter()
+            .map(|item| match item {
+                Value::String(ty) => parse_type(ty),
+                Value::Array(parts) => match parts.as_slice() {
+                    [Value::String(kind), Value::String(dtype), Value::Number(_), dims @ ..]
+                        if kind == "ndarray" =>
+                    {
+                        IreeArgumentType::Tensor {
+                            dtype: dtype.clone(),
+                            shape: dims
+                                .iter()
+                                .map(|dim| dim.as_u64().map(|dim| dim as usize))
+                                .collect(),
+                        }
+                    }
+                    _ => IreeArgumentType::Ref,
+                },
+                _ => IreeArgumentType::Ref,
+            })
+            .collect()
+    };
+    Some((list("a"), list("r")))
+}
@@ -1,6 +1,11 @@
 use iree_sys::{
     helper::IREE_CHECK_OK,
-    iree::runtime::api::{iree_status_t, iree_status_to_string},
+    iree::{
+        runtime::api::{
+            iree_status_allocate, iree_status_code_t, iree_status_t, iree_status_to_string,
+        },
+        shim::{iree_sys_make_string_view, iree_sys_status_code},
+    },
 };
 
 use crate::err::IreeError;
@@ -12,6 +17,11 @@ pub struct IreeStatus {
     pub(crate) status: iree_status_t,
 }
 
+// a status is an allocation owned by whoever holds it, not tied to the thread that created it, so
+// errors can be passed between threads
+unsafe impl Send for IreeStatus {}
+unsafe impl Sync for IreeStatus {}
+
 impl From<iree_status_t> for IreeStatus {
     fn from(status: iree_status_t) -> Self {
         Self { status }
@@ -19,9 +29,26 @@ impl From<iree_status_t> for IreeStatus {
 }
 
 impl IreeStatus {
+    /// Allocates a new status with the given code and message, e.g. to hand an error from Rust back to IREE.
+    pub(crate) fn allocate(code: iree_status_code_t, message: &str) -> Self {
+        let status = unsafe {
+            // not the variadic iree_status_allocate_f, which can't be called through `dynamic`
+            iree_status_allocate(
+                code,
+                concat!(file!(), "\0").as_ptr() as _,
+                line!(),
+                iree_sys_make_string_view(message.as_ptr() as _, message.len()),
+            )
+        };
+        Self { status }
+    }
+
     pub fn is_ok(&self) -> bool {
         unsafe { IREE_CHECK_OK(self.status) }
     }
+    pub fn code(&self) -> iree_status_code_t {
+        unsafe { iree_sys_status_code(self.status) }
+    }
     pub fn to_string(&self, allocator: &IreeAllocator) -> Result<String, IreeError> {
         let mut out_buffer = std::mem::MaybeUninit::<*mut u8>::uninit();
         let mut out_buffer_length = std::mem::MaybeUninit::<usize>::uninit();
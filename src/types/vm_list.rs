@@ -0,0 +1,137 @@
+use iree_sys::{
+    helper::IREE_CHECK_OK,
+    iree::{
+        runtime::api::{
+            iree_hal_buffer_view_deref, iree_status_ignore, iree_vm_list_deref,
+            iree_vm_list_get_ref_retain, iree_vm_list_get_value, iree_vm_list_release,
+            iree_vm_list_retain, iree_vm_list_size, iree_vm_list_t, iree_vm_ref_release,
+            iree_vm_ref_t, iree_vm_value_t, iree_vm_value_type_e_IREE_VM_VALUE_TYPE_F32,
+            iree_vm_value_type_e_IREE_VM_VALUE_TYPE_F64,
+            iree_vm_value_type_e_IREE_VM_VALUE_TYPE_I32,
+            iree_vm_value_type_e_IREE_VM_VALUE_TYPE_I64,
+        },
+        shim::{
+            iree_sys_vm_value_get_f32, iree_sys_vm_value_get_f64, iree_sys_vm_value_get_i32,
+            iree_sys_vm_value_get_i64,
+        },
+    },
+};
+
+use crate::err::IreeError;
+
+use super::{
+    allocator::IreeAllocator,
+    hal_buffer::IreeHalBufferView,
+    status::IreeStatus,
+    vm_value::{IreeVmValue, IreeVmValueType},
+};
+
+/// A reference counted VM list, e.g. a list returned by a function.
+pub struct IreeVmList {
+    pub(crate) list_ptr: *mut iree_vm_list_t,
+}
+
+impl IreeVmList {
+    pub fn len(&self) -> usize {
+        unsafe { iree_vm_list_size(self.list_ptr) }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns element `i`. Buffer views and lists are retained, not copied.
+    pub fn get(&self, i: usize) -> Result<IreeVmValue, IreeError> {
+        unsafe { list_get(self.list_ptr, i, None) }
+    }
+
+    pub fn to_vec(&self) -> Result<Vec<IreeVmValue>, IreeError> {
+        (0..self.len()).map(|i| self.get(i)).collect()
+    }
+}
+
+impl Clone for IreeVmList {
+    fn clone(&self) -> Self {
+        unsafe {
+            iree_vm_list_retain(self.list_ptr);
+        }
+        Self {
+            list_ptr: self.list_ptr,
+        }
+    }
+}
+
+impl Drop for IreeVmList {
+    fn drop(&mut self) {
+        unsafe {
+            iree_vm_list_release(self.list_ptr);
+        }
+    }
+}
+
+/// Wraps a retained reference, taking over its ownership.
+pub(crate) unsafe fn ref_value(mut vm_ref: iree_vm_ref_t) -> Result<IreeVmValue, IreeError> {
+    let buffer_view_ptr = iree_hal_buffer_view_deref(vm_ref);
+    if !buffer_view_ptr.is_null() {
+        return Ok(IreeVmValue::BufferView(IreeHalBufferView {
+            buffer_view_ptr,
+        }));
+    }
+    let list_ptr = iree_vm_list_deref(vm_ref);
+    if !list_ptr.is_null() {
+        return Ok(IreeVmValue::List(IreeVmList { list_ptr }));
+    }
+    iree_vm_ref_release(&mut vm_ref);
+    Err(
+        "unsupported reference type, expected a buffer view or a list"
+            .to_string()
+            .into(),
+    )
+}
+
+/// Reads element `i` of `list`. With `value_type` unknown, the element is tried as a reference
+/// first and then as a primitive value.
+pub(crate) unsafe fn list_get(
+    list: *mut iree_vm_list_t,
+    i: usize,
+    value_type: Option<IreeVmValueType>,
+) -> Result<IreeVmValue, IreeError> {
+    if matches!(value_type, None | Some(IreeVmValueType::Ref)) {
+        let mut vm_ref = iree_vm_ref_t::default();
+        let status = iree_vm_list_get_ref_retain(list, i, &mut vm_ref);
+        if IREE_CHECK_OK(status) {
+            return ref_value(vm_ref);
+        }
+        if value_type.is_some() {
+            return Err(IreeError::from_status(
+                IreeStatus { status },
+                &IreeAllocator::system_allocator(),
+            ));
+        }
+        iree_status_ignore(status);
+    }
+
+    let mut value = iree_vm_value_t::default();
+    let status = iree_vm_list_get_value(list, i, &mut value);
+    if !IREE_CHECK_OK(status) {
+        return Err(IreeError::from_status(
+            IreeStatus { status },
+            &IreeAllocator::system_allocator(),
+        ));
+    }
+    match value.type_ {
+        t if t == iree_vm_value_type_e_IREE_VM_VALUE_TYPE_I32 => {
+            Ok(IreeVmValue::I32(iree_sys_vm_value_get_i32(&mut value)))
+        }
+        t if t == iree_vm_value_type_e_IREE_VM_VALUE_TYPE_I64 => {
+            Ok(IreeVmValue::I64(iree_sys_vm_value_get_i64(&mut value)))
+        }
+        t if t == iree_vm_value_type_e_IREE_VM_VALUE_TYPE_F32 => {
+            Ok(IreeVmValue::F32(iree_sys_vm_value_get_f32(&mut value)))
+        }
+        t if t == iree_vm_value_type_e_IREE_VM_VALUE_TYPE_F64 => {
+            Ok(IreeVmValue::F64(iree_sys_vm_value_get_f64(&mut value)))
+        }
+        t => Err(format!("unsupported value type {}", t.0).into()),
+    }
+}
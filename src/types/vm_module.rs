@@ -0,0 +1,134 @@
+use std::{ffi::c_void, io::Read, path::Path};
+
+use iree_sys::{
+    helper::IREE_CHECK_OK,
+    iree::runtime::api::{
+        iree_allocator_free, iree_allocator_malloc, iree_const_byte_span_t,
+        iree_runtime_instance_vm_instance, iree_vm_bytecode_module_create, iree_vm_module_name,
+        iree_vm_module_release, iree_vm_module_retain, iree_vm_module_t,
+    },
+};
+
+use crate::err::IreeError;
+
+use super::{runtime::instance::IreeRuntimeInstance, status::IreeStatus};
+
+/// A reference counted handle to a VM module that can be appended to sessions.
+///
+/// Modules are immutable, so one module can be appended to any number of sessions of the instance
+/// it was created with; each session only allocates its own module state.
+pub struct IreeVmModule {
+    pub(crate) module_ptr: *mut iree_vm_module_t,
+}
+
+impl IreeVmModule {
+    /// Loads a bytecode module (`.vmfb`). `data` is copied once into memory owned by the module.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, fields(bytes = data.len()), err)
+    )]
+    pub fn from_bytecode(instance: &IreeRuntimeInstance, data: &[u8]) -> Result<Self, IreeError> {
+        Self::create_bytecode(instance, data.len(), |contents| {
+            contents.copy_from_slice(data);
+            Ok(())
+        })
+    }
+
+    /// Loads a bytecode module (`.vmfb`) from a file, reading it directly into memory owned by the
+    /// module.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, fields(path = %path.as_ref().display()), err)
+    )]
+    pub fn from_file(
+        instance: &IreeRuntimeInstance,
+        path: impl AsRef<Path>,
+    ) -> Result<Self, IreeError> {
+        let path = path.as_ref();
+        let io_error =
+            |err: std::io::Error| IreeError::from(format!("{}: {}", path.display(), err));
+        let mut file = std::fs::File::open(path).map_err(io_error)?;
+        let length = file.metadata().map_err(io_error)?.len() as usize;
+        Self::create_bytecode(instance, length, |contents| {
+            file.read_exact(contents).map_err(io_error)
+        })
+    }
+
+    /// Allocates `length` bytes with the instance's host allocator, fills them and creates a module
+    /// that frees them when it is destroyed.
+    fn create_bytecode<F>(
+        instance: &IreeRuntimeInstance,
+        length: usize,
+        fill: F,
+    ) -> Result<Self, IreeError>
+    where
+        F: FnOnce(&mut [u8]) -> Result<(), IreeError>,
+    {
+        let allocator = instance.host_allocator();
+        let mut contents = std::ptr::null_mut::<c_void>();
+        unsafe {
+            let status = iree_allocator_malloc(allocator.allocator, length, &mut contents);
+            if !IREE_CHECK_OK(status) {
+                return Err(IreeError::from_status(IreeStatus { status }, &allocator));
+            }
+            let slice = std::slice::from_raw_parts_mut(contents as *mut u8, length);
+            if let Err(err) = fill(slice) {
+                iree_allocator_free(allocator.allocator, contents);
+                return Err(err);
+            }
+
+            let mut module_ptr = std::mem::MaybeUninit::<*mut iree_vm_module_t>::uninit();
+            let status = iree_vm_bytecode_module_create(
+                iree_runtime_instance_vm_instance(instance.instance_ptr),
+                iree_const_byte_span_t {
+                    data: contents as _,
+                    data_length: length,
+                },
+                allocator.allocator,
+                allocator.allocator,
+                module_ptr.as_mut_ptr(),
+            );
+            if !IREE_CHECK_OK(status) {
+                // the module only takes ownership of the contents once it has been created
+                iree_allocator_free(allocator.allocator, contents);
+                return Err(IreeError::from_status(IreeStatus { status }, &allocator));
+            }
+            Ok(Self {
+                module_ptr: module_ptr.assume_init(),
+            })
+        }
+    }
+
+    pub fn name(&self) -> String {
+        unsafe {
+            let name = iree_vm_module_name(self.module_ptr);
+            let bytes = std::slice::from_raw_parts(name.data as *const u8, name.size);
+            String::from_utf8_lossy(bytes).to_string()
+        }
+    }
+}
+
+// modules are immutable after creation and reference counted atomically. Native modules keep
+// their functions and per-context state in Rust, which IreeVmNativeModuleBuilder requires to be
+// Send + Sync.
+unsafe impl Send for IreeVmModule {}
+unsafe impl Sync for IreeVmModule {}
+
+impl Clone for IreeVmModule {
+    fn clone(&self) -> Self {
+        unsafe {
+            iree_vm_module_retain(self.module_ptr);
+        }
+        Self {
+            module_ptr: self.module_ptr,
+        }
+    }
+}
+
+impl Drop for IreeVmModule {
+    fn drop(&mut self) {
+        unsafe {
+            iree_vm_module_release(self.module_ptr);
+        }
+    }
+}
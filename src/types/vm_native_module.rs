@@ -0,0 +1,411 @@
+use std::{
+    collections::BTreeMap,
+    ffi::c_void,
+    panic::{catch_unwind, AssertUnwindSafe},
+    ptr::null_mut,
+    sync::Arc,
+};
+
+use iree_sys::{
+    helper::IREE_CHECK_OK,
+    iree::runtime::api::{
+        iree_allocator_t, iree_byte_span_t, iree_hal_buffer_view_retain_ref,
+        iree_runtime_instance_host_allocator, iree_runtime_instance_vm_instance,
+        iree_status_code_e_IREE_STATUS_INTERNAL, iree_status_code_e_IREE_STATUS_INVALID_ARGUMENT,
+        iree_status_code_t, iree_status_t, iree_string_view_t, iree_vm_function_call_t,
+        iree_vm_list_retain_ref, iree_vm_module_initialize, iree_vm_module_state_t,
+        iree_vm_module_t, iree_vm_native_export_descriptor_t, iree_vm_native_function_ptr_t,
+        iree_vm_native_module_create, iree_vm_native_module_descriptor_t, iree_vm_ref_retain,
+        iree_vm_ref_t, iree_vm_stack_frame_t,
+        iree_vm_stack_frame_type_e_IREE_VM_STACK_FRAME_NATIVE, iree_vm_stack_function_enter,
+        iree_vm_stack_function_leave, iree_vm_stack_t,
+    },
+};
+
+use crate::err::IreeError;
+
+use super::{
+    runtime::instance::IreeRuntimeInstance,
+    status::IreeStatus,
+    vm_list::ref_value,
+    vm_module::IreeVmModule,
+    vm_value::{IreeVmValue, IreeVmValueType},
+};
+
+/// Callback implementing a native function. Receives the per-context module state and the call arguments.
+pub type IreeVmNativeFunction<S> =
+    dyn Fn(&mut S, Vec<IreeVmValue>) -> Result<Vec<IreeVmValue>, IreeError> + Send + Sync;
+
+struct NativeFunction<S> {
+    name: String,
+    calling_convention: String,
+    arguments: Vec<IreeVmValueType>,
+    results: Vec<IreeVmValueType>,
+    callback: Arc<IreeVmNativeFunction<S>>,
+}
+
+impl<S> Clone for NativeFunction<S> {
+    fn clone(&self) -> Self {
+        Self {
+            name: self.name.clone(),
+            calling_convention: self.calling_convention.clone(),
+            arguments: self.arguments.clone(),
+            results: self.results.clone(),
+            callback: self.callback.clone(),
+        }
+    }
+}
+
+/// Builds a VM module whose functions are implemented in Rust.
+///
+/// Each VM context the module is registered in gets its own state `S`, created by the state factory.
+/// Compiled programs can import the functions as `<module name>.<function name>`. The module is
+/// shared between threads like any `IreeVmModule`, so `S` must be `Send + Sync`.
+pub struct IreeVmNativeModuleBuilder<S> {
+    name: String,
+    state_factory: Arc<dyn Fn() -> S + Send + Sync>,
+    functions: Vec<NativeFunction<S>>,
+}
+
+impl<S: Default + Send + Sync + 'static> IreeVmNativeModuleBuilder<S> {
+    pub fn new(name: &str) -> Self {
+        Self::with_state(name, S::default)
+    }
+}
+
+impl<S: Send + Sync + 'static> IreeVmNativeModuleBuilder<S> {
+    pub fn with_state<F>(name: &str, state_factory: F) -> Self
+    where
+        F: Fn() -> S + Send + Sync + 'static,
+    {
+        Self {
+            name: name.to_string(),
+            state_factory: Arc::new(state_factory),
+            functions: Vec::new(),
+        }
+    }
+
+    /// Adds an exported function with the given signature. Names must be unique within the module.
+    pub fn function<F>(
+        &mut self,
+        name: &str,
+        arguments: &[IreeVmValueType],
+        results: &[IreeVmValueType],
+        callback: F,
+    ) -> &mut Self
+    where
+        F: Fn(&mut S, Vec<IreeVmValue>) -> Result<Vec<IreeVmValue>, IreeError>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.functions.push(NativeFunction {
+            name: name.to_string(),
+            calling_convention: IreeVmValueType::cconv(arguments, results),
+            arguments: arguments.to_vec(),
+            results: results.to_vec(),
+            callback: Arc::new(callback),
+        });
+        self
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, fields(module = %self.name, functions = self.functions.len()), err)
+    )]
+    pub fn build(&self, instance: &IreeRuntimeInstance) -> Result<IreeVmModule, IreeError> {
+        // IREE requires the exports sorted by name, and call ordinals index into that order
+        let mut functions = BTreeMap::new();
+        for function in self.functions.iter() {
+            if functions.insert(function.name.as_str(), function).is_some() {
+                return Err(format!(
+                    "native module '{}' declares function '{}' more than once",
+                    self.name, function.name
+                )
+                .into());
+            }
+        }
+        let mut module = Box::new(NativeModule {
+            name: self.name.clone(),
+            state_factory: self.state_factory.clone(),
+            functions: functions.into_values().cloned().collect(),
+            exports: Vec::new(),
+            function_ptrs: Vec::new(),
+            descriptor: iree_vm_native_module_descriptor_t::default(),
+        });
+        // the descriptor points into the boxed module, so it has to be filled in after boxing
+        module.exports = module
+            .functions
+            .iter()
+            .map(|f| iree_vm_native_export_descriptor_t {
+                local_name: string_view(&f.name),
+                calling_convention: string_view(&f.calling_convention),
+                ..Default::default()
+            })
+            .collect();
+        module.function_ptrs =
+            vec![iree_vm_native_function_ptr_t::default(); module.functions.len()];
+        module.descriptor = iree_vm_native_module_descriptor_t {
+            name: string_view(&module.name),
+            export_count: module.exports.len() as _,
+            exports: module.exports.as_ptr(),
+            function_count: module.function_ptrs.len() as _,
+            functions: module.function_ptrs.as_ptr(),
+            ..Default::default()
+        };
+
+        let allocator = instance.host_allocator();
+        let module_ptr = Box::into_raw(module);
+        let mut interface = iree_vm_module_t::default();
+        let mut out_module = std::mem::MaybeUninit::<*mut iree_vm_module_t>::uninit();
+        unsafe {
+            let status = iree_vm_module_initialize(&mut interface, module_ptr as *mut c_void);
+            if !IREE_CHECK_OK(status) {
+                drop(Box::from_raw(module_ptr));
+                return Err(IreeError::from_status(IreeStatus { status }, &allocator));
+            }
+            interface.destroy = Some(native_module_destroy::<S>);
+            interface.alloc_state = Some(native_module_alloc_state::<S>);
+            interface.free_state = Some(native_module_free_state::<S>);
+            interface.begin_call = Some(native_module_begin_call::<S>);
+
+            let status = iree_vm_native_module_create(
+                &interface,
+                &(*module_ptr).descriptor,
+                iree_runtime_instance_vm_instance(instance.instance_ptr),
+                iree_runtime_instance_host_allocator(instance.instance_ptr),
+                out_module.as_mut_ptr(),
+            );
+            if !IREE_CHECK_OK(status) {
+                drop(Box::from_raw(module_ptr));
+                return Err(IreeError::from_status(IreeStatus { status }, &allocator));
+            }
+            Ok(IreeVmModule {
+                module_ptr: out_module.assume_init(),
+            })
+        }
+    }
+}
+
+/// Backing storage of a native module, owned by IREE once the module has been created.
+struct NativeModule<S> {
+    name: String,
+    state_factory: Arc<dyn Fn() -> S + Send + Sync>,
+    /// Sorted by name, so that a function's ordinal is its index.
+    functions: Vec<NativeFunction<S>>,
+    exports: Vec<iree_vm_native_export_descriptor_t>,
+    function_ptrs: Vec<iree_vm_native_function_ptr_t>,
+    descriptor: iree_vm_native_module_descriptor_t,
+}
+
+fn string_view(s: &str) -> iree_string_view_t {
+    iree_string_view_t {
+        data: s.as_ptr() as _,
+        size: s.len(),
+    }
+}
+
+fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        s.to_string()
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
+
+fn make_status(code: iree_status_code_t, message: &str) -> iree_status_t {
+    IreeStatus::allocate(code, message).status
+}
+
+unsafe extern "C" fn native_module_destroy<S>(self_: *mut c_void) {
+    drop(Box::from_raw(self_ as *mut NativeModule<S>));
+}
+
+unsafe extern "C" fn native_module_alloc_state<S>(
+    self_: *mut c_void,
+    _allocator: iree_allocator_t,
+    out_module_state: *mut *mut iree_vm_module_state_t,
+) -> iree_status_t {
+    let module = &*(self_ as *const NativeModule<S>);
+    match catch_unwind(AssertUnwindSafe(|| (module.state_factory)())) {
+        Ok(state) => {
+            *out_module_state = Box::into_raw(Box::new(state)) as *mut iree_vm_module_state_t;
+            null_mut()
+        }
+        Err(payload) => make_status(
+            iree_status_code_e_IREE_STATUS_INTERNAL,
+            &format!(
+                "panic while creating state of native module '{}': {}",
+                module.name,
+                panic_message(payload.as_ref())
+            ),
+        ),
+    }
+}
+
+unsafe extern "C" fn native_module_free_state<S>(
+    _self_: *mut c_void,
+    module_state: *mut iree_vm_module_state_t,
+) {
+    if !module_state.is_null() {
+        drop(Box::from_raw(module_state as *mut S));
+    }
+}
+
+unsafe extern "C" fn native_module_begin_call<S>(
+    self_: *mut c_void,
+    stack: *mut iree_vm_stack_t,
+    call: iree_vm_function_call_t,
+) -> iree_status_t {
+    let module = &*(self_ as *const NativeModule<S>);
+    let function = match module.functions.get(call.function.ordinal as usize) {
+        Some(function) => function,
+        None => {
+            return make_status(
+                iree_status_code_e_IREE_STATUS_INVALID_ARGUMENT,
+                &format!(
+                    "function ordinal {} out of range in native module '{}'",
+                    call.function.ordinal, module.name
+                ),
+            )
+        }
+    };
+
+    let mut frame: *mut iree_vm_stack_frame_t = null_mut();
+    let status = iree_vm_stack_function_enter(
+        stack,
+        &call.function,
+        iree_vm_stack_frame_type_e_IREE_VM_STACK_FRAME_NATIVE,
+        0,
+        None,
+        &mut frame,
+    );
+    if !IREE_CHECK_OK(status) {
+        return status;
+    }
+    let state = &mut *((*frame).module_state as *mut S);
+
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        let arguments = read_arguments(&function.arguments, call.arguments)?;
+        let results = (function.callback)(state, arguments)?;
+        write_results(&function.results, results, call.results)
+    }));
+
+    let status = match result {
+        Ok(Ok(())) => null_mut(),
+        Ok(Err(e)) => make_status(
+            iree_status_code_e_IREE_STATUS_INTERNAL,
+            &format!("{}.{}: {}", module.name, function.name, e),
+        ),
+        Err(payload) => make_status(
+            iree_status_code_e_IREE_STATUS_INTERNAL,
+            &format!(
+                "panic in native function '{}.{}': {}",
+                module.name,
+                function.name,
+                panic_message(payload.as_ref())
+            ),
+        ),
+    };
+    if !IREE_CHECK_OK(status) {
+        return status;
+    }
+    iree_vm_stack_function_leave(stack)
+}
+
+fn value_size(value_type: IreeVmValueType) -> usize {
+    match value_type {
+        IreeVmValueType::I32 | IreeVmValueType::F32 => 4,
+        IreeVmValueType::I64 | IreeVmValueType::F64 => 8,
+        IreeVmValueType::Ref => std::mem::size_of::<iree_vm_ref_t>(),
+    }
+}
+
+/// Unpacks the arguments of a call. Arguments are tightly packed in calling convention order.
+unsafe fn read_arguments(
+    types: &[IreeVmValueType],
+    storage: iree_byte_span_t,
+) -> Result<Vec<IreeVmValue>, IreeError> {
+    let required: usize = types.iter().map(|t| value_size(*t)).sum();
+    if storage.data_length < required {
+        return Err(format!(
+            "argument storage is {} bytes but the signature requires {}",
+            storage.data_length, required
+        )
+        .into());
+    }
+    let mut ptr = storage.data as *const u8;
+    let mut arguments = Vec::with_capacity(types.len());
+    for (i, value_type) in types.iter().enumerate() {
+        let value = match value_type {
+            IreeVmValueType::I32 => IreeVmValue::I32((ptr as *const i32).read_unaligned()),
+            IreeVmValueType::I64 => IreeVmValue::I64((ptr as *const i64).read_unaligned()),
+            IreeVmValueType::F32 => IreeVmValue::F32((ptr as *const f32).read_unaligned()),
+            IreeVmValueType::F64 => IreeVmValue::F64((ptr as *const f64).read_unaligned()),
+            IreeVmValueType::Ref => {
+                let mut vm_ref = (ptr as *const iree_vm_ref_t).read_unaligned();
+                // the caller keeps its reference, so take our own
+                let mut retained = iree_vm_ref_t::default();
+                iree_vm_ref_retain(&mut vm_ref, &mut retained);
+                ref_value(retained).map_err(|_| {
+                    IreeError::from(format!("argument {} is not a buffer view or a list", i))
+                })?
+            }
+        };
+        arguments.push(value);
+        ptr = ptr.add(value_size(*value_type));
+    }
+    Ok(arguments)
+}
+
+/// Packs the results of a call, checking them against the declared signature.
+unsafe fn write_results(
+    types: &[IreeVmValueType],
+    results: Vec<IreeVmValue>,
+    storage: iree_byte_span_t,
+) -> Result<(), IreeError> {
+    if results.len() != types.len() {
+        return Err(format!(
+            "expected {} results but the function returned {}",
+            types.len(),
+            results.len()
+        )
+        .into());
+    }
+    let required: usize = types.iter().map(|t| value_size(*t)).sum();
+    if storage.data_length < required {
+        return Err(format!(
+            "result storage is {} bytes but the signature requires {}",
+            storage.data_length, required
+        )
+        .into());
+    }
+    let mut ptr = storage.data;
+    for (i, (value, value_type)) in results.into_iter().zip(types).enumerate() {
+        if value.value_type() != *value_type {
+            return Err(format!(
+                "result {} has type {:?} but the signature declares {:?}",
+                i,
+                value.value_type(),
+                value_type
+            )
+            .into());
+        }
+        match value {
+            IreeVmValue::I32(v) => (ptr as *mut i32).write_unaligned(v),
+            IreeVmValue::I64(v) => (ptr as *mut i64).write_unaligned(v),
+            IreeVmValue::F32(v) => (ptr as *mut f32).write_unaligned(v),
+            IreeVmValue::F64(v) => (ptr as *mut f64).write_unaligned(v),
+            // ownership of the new reference passes to the caller
+            IreeVmValue::BufferView(v) => (ptr as *mut iree_vm_ref_t)
+                .write_unaligned(iree_hal_buffer_view_retain_ref(v.buffer_view_ptr)),
+            IreeVmValue::List(v) => {
+                (ptr as *mut iree_vm_ref_t).write_unaligned(iree_vm_list_retain_ref(v.list_ptr))
+            }
+        }
+        ptr = ptr.add(value_size(*value_type));
+    }
+    Ok(())
+}
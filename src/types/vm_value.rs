@@ -0,0 +1,89 @@
+use super::{hal_buffer::IreeHalBufferView, vm_list::IreeVmList};
+
+/// The type of a value passed across the VM calling convention.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IreeVmValueType {
+    I32,
+    I64,
+    F32,
+    F64,
+    Ref,
+}
+
+impl IreeVmValueType {
+    /// Returns the character used for this type in VM calling convention strings (e.g. `0ri_r`).
+    pub fn cconv_char(&self) -> char {
+        match self {
+            IreeVmValueType::I32 => 'i',
+            IreeVmValueType::I64 => 'I',
+            IreeVmValueType::F32 => 'f',
+            IreeVmValueType::F64 => 'F',
+            IreeVmValueType::Ref => 'r',
+        }
+    }
+
+    /// Parses a single calling convention character.
+    pub fn from_cconv_char(c: char) -> Option<Self> {
+        match c {
+            'i' => Some(IreeVmValueType::I32),
+            'I' => Some(IreeVmValueType::I64),
+            'f' => Some(IreeVmValueType::F32),
+            'F' => Some(IreeVmValueType::F64),
+            'r' => Some(IreeVmValueType::Ref),
+            _ => None,
+        }
+    }
+
+    /// Builds a calling convention string for the given argument and result types.
+    pub fn cconv(arguments: &[IreeVmValueType], results: &[IreeVmValueType]) -> String {
+        let fragment = |types: &[IreeVmValueType]| {
+            if types.is_empty() {
+                "v".to_string()
+            } else {
+                types.iter().map(|t| t.cconv_char()).collect()
+            }
+        };
+        format!("0{}_{}", fragment(arguments), fragment(results))
+    }
+}
+
+/// A value passed to or returned from a VM function.
+pub enum IreeVmValue {
+    I32(i32),
+    I64(i64),
+    F32(f32),
+    F64(f64),
+    BufferView(IreeHalBufferView),
+    List(IreeVmList),
+}
+
+impl IreeVmValue {
+    pub fn value_type(&self) -> IreeVmValueType {
+        match self {
+            IreeVmValue::I32(_) => IreeVmValueType::I32,
+            IreeVmValue::I64(_) => IreeVmValueType::I64,
+            IreeVmValue::F32(_) => IreeVmValueType::F32,
+            IreeVmValue::F64(_) => IreeVmValueType::F64,
+            IreeVmValue::BufferView(_) | IreeVmValue::List(_) => IreeVmValueType::Ref,
+        }
+    }
+}
+
+impl std::fmt::Debug for IreeVmValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IreeVmValue::I32(v) => write!(f, "I32({})", v),
+            IreeVmValue::I64(v) => write!(f, "I64({})", v),
+            IreeVmValue::F32(v) => write!(f, "F32({})", v),
+            IreeVmValue::F64(v) => write!(f, "F64({})", v),
+            IreeVmValue::BufferView(v) => match v.shape() {
+                Ok(shape) => write!(f, "BufferView({:?})", shape),
+                Err(_) => write!(f, "BufferView(?)"),
+            },
+            IreeVmValue::List(v) => match v.to_vec() {
+                Ok(values) => write!(f, "List({:?})", values),
+                Err(_) => write!(f, "List(?)"),
+            },
+        }
+    }
+}
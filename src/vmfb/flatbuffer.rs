@@ -0,0 +1,146 @@
+//! A minimal bounds-checked FlatBuffer reader, enough to walk vmfb modules without generated code.
+//! Malformed input is reported as `IreeErrorKind::InvalidVmfb` instead of panicking.
+
+use crate::err::{IreeError, IreeErrorKind};
+
+pub(crate) fn invalid(message: &str) -> IreeError {
+    IreeError::new(IreeErrorKind::InvalidVmfb(message.to_string()))
+}
+
+fn read<const N: usize>(buf: &[u8], pos: usize) -> Result<[u8; N], IreeError> {
+    pos.checked_add(N)
+        .and_then(|end| buf.get(pos..end))
+        .map(|bytes| bytes.try_into().unwrap())
+        .ok_or_else(|| invalid("offset out of bounds"))
+}
+
+pub(crate) fn read_u16(buf: &[u8], pos: usize) -> Result<u16, IreeError> {
+    Ok(u16::from_le_bytes(read(buf, pos)?))
+}
+
+pub(crate) fn read_u32(buf: &[u8], pos: usize) -> Result<u32, IreeError> {
+    Ok(u32::from_le_bytes(read(buf, pos)?))
+}
+
+pub(crate) fn read_i32(buf: &[u8], pos: usize) -> Result<i32, IreeError> {
+    Ok(i32::from_le_bytes(read(buf, pos)?))
+}
+
+pub(crate) fn read_u64(buf: &[u8], pos: usize) -> Result<u64, IreeError> {
+    Ok(u64::from_le_bytes(read(buf, pos)?))
+}
+
+/// Follows the unsigned offset stored at `pos`.
+fn deref(buf: &[u8], pos: usize) -> Result<usize, IreeError> {
+    pos.checked_add(read_u32(buf, pos)? as usize)
+        .filter(|target| *target < buf.len())
+        .ok_or_else(|| invalid("offset out of bounds"))
+}
+
+#[derive(Clone, Copy)]
+pub(crate) struct Table<'a> {
+    buf: &'a [u8],
+    pos: usize,
+    vtable: usize,
+    vtable_len: usize,
+}
+
+impl<'a> Table<'a> {
+    /// The root table of a (not size-prefixed) buffer.
+    pub(crate) fn root(buf: &'a [u8]) -> Result<Self, IreeError> {
+        Self::at(buf, deref(buf, 0)?)
+    }
+
+    fn at(buf: &'a [u8], pos: usize) -> Result<Self, IreeError> {
+        let vtable = pos as i64 - read_i32(buf, pos)? as i64;
+        if vtable < 0 {
+            return Err(invalid("vtable out of bounds"));
+        }
+        let vtable = vtable as usize;
+        let vtable_len = read_u16(buf, vtable)? as usize;
+        if vtable_len < 4 || vtable + vtable_len > buf.len() {
+            return Err(invalid("vtable out of bounds"));
+        }
+        Ok(Self {
+            buf,
+            pos,
+            vtable,
+            vtable_len,
+        })
+    }
+
+    /// Position of field `index`, or `None` if it is not present.
+    fn field(&self, index: usize) -> Result<Option<usize>, IreeError> {
+        let entry = 4 + 2 * index;
+        if entry + 2 > self.vtable_len {
+            return Ok(None);
+        }
+        match read_u16(self.buf, self.vtable + entry)? {
+            0 => Ok(None),
+            offset => Ok(Some(self.pos + offset as usize)),
+        }
+    }
+
+    pub(crate) fn u32(&self, index: usize, default: u32) -> Result<u32, IreeError> {
+        match self.field(index)? {
+            Some(pos) => read_u32(self.buf, pos),
+            None => Ok(default),
+        }
+    }
+
+    pub(crate) fn u64(&self, index: usize, default: u64) -> Result<u64, IreeError> {
+        match self.field(index)? {
+            Some(pos) => read_u64(self.buf, pos),
+            None => Ok(default),
+        }
+    }
+
+    pub(crate) fn table(&self, index: usize) -> Result<Option<Table<'a>>, IreeError> {
+        match self.field(index)? {
+            Some(pos) => Ok(Some(Self::at(self.buf, deref(self.buf, pos)?)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Start and element count of the vector in field `index`.
+    fn vector(&self, index: usize) -> Result<Option<(usize, usize)>, IreeError> {
+        match self.field(index)? {
+            Some(pos) => {
+                let start = deref(self.buf, pos)?;
+                let len = read_u32(self.buf, start)? as usize;
+                Ok(Some((start + 4, len)))
+            }
+            None => Ok(None),
+        }
+    }
+
+    pub(crate) fn bytes(&self, index: usize) -> Result<Option<&'a [u8]>, IreeError> {
+        match self.vector(index)? {
+            Some((start, len)) => start
+                .checked_add(len)
+                .and_then(|end| self.buf.get(start..end))
+                .map(Some)
+                .ok_or_else(|| invalid("vector out of bounds")),
+            None => Ok(None),
+        }
+    }
+
+    pub(crate) fn string(&self, index: usize) -> Result<Option<&'a str>, IreeError> {
+        match self.bytes(index)? {
+            Some(bytes) => std::str::from_utf8(bytes)
+                .map(Some)
+                .map_err(|_| invalid("string is not UTF-8")),
+            None => Ok(None),
+        }
+    }
+
+    /// Tables of the vector in field `index`; empty if the field is not present.
+    pub(crate) fn tables(&self, index: usize) -> Result<Vec<Table<'a>>, IreeError> {
+        match self.vector(index)? {
+            Some((start, len)) => (0..len)
+                .map(|i| Self::at(self.buf, deref(self.buf, start + 4 * i)?))
+                .collect(),
+            None => Ok(Vec::new()),
+        }
+    }
+}
@@ -0,0 +1,331 @@
+use std::fmt::Display;
+
+use crate::err::IreeError;
+
+use super::{
+    flatbuffer::{read_u16, Table},
+    module::IreeVmfbModule,
+};
+
+// Field indices in runtime/src/iree/schemas/bytecode_module_def.fbs at `IREE_REVISION`
+// (iree-3.1.0), see module.rs.
+
+// fields of `iree.vm.BytecodeModuleDef`
+const MODULE_ATTRS: usize = 2; // attrs: [AttrDef]
+const MODULE_DEPENDENCIES: usize = 4; // dependencies: [ModuleDependencyDef]
+const MODULE_IMPORTED_FUNCTIONS: usize = 5; // imported_functions: [ImportFunctionDef]
+const MODULE_EXPORTED_FUNCTIONS: usize = 6; // exported_functions: [ExportFunctionDef]
+
+// fields of `iree.vm.ModuleDependencyDef`
+const DEPENDENCY_NAME: usize = 0; // name: string
+const DEPENDENCY_MINIMUM_VERSION: usize = 1; // minimum_version: uint32
+const DEPENDENCY_FLAGS: usize = 2; // flags: ModuleDependencyFlagBits
+
+// fields of `iree.vm.ImportFunctionDef` and `iree.vm.ExportFunctionDef`
+const FUNCTION_NAME: usize = 0; // full_name / local_name: string
+const FUNCTION_SIGNATURE: usize = 1; // signature: FunctionSignatureDef
+const IMPORT_FLAGS: usize = 2; // flags: ImportFlagBits (ImportFunctionDef only)
+
+// fields of `iree.vm.FunctionSignatureDef` and `iree.vm.AttrDef`
+const SIGNATURE_CALLING_CONVENTION: usize = 0; // calling_convention: string
+const SIGNATURE_ATTRS: usize = 1; // attrs: [AttrDef]
+const ATTR_KEY: usize = 0; // key: string
+const ATTR_VALUE: usize = 1; // value: string
+
+/// `REQUIRED` is the default of both `ImportFlagBits` and `ModuleDependencyFlagBits`.
+const FLAGS_REQUIRED: u32 = 1 << 0;
+const FLAGS_OPTIONAL: u32 = 1 << 1;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct IreeVmfbSignature<'a> {
+    /// e.g. `0rr_r` for two refs in and one ref out
+    pub calling_convention: &'a str,
+    /// Reflection attributes, e.g. `iree.abi.declaration`.
+    pub attrs: Vec<(&'a str, &'a str)>,
+}
+
+impl Display for IreeVmfbSignature<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.calling_convention)?;
+        for (i, (key, value)) in self.attrs.iter().enumerate() {
+            let separator = if i == 0 { " {" } else { ", " };
+            write!(f, "{}{} = {:?}", separator, key, value)?;
+        }
+        if !self.attrs.is_empty() {
+            write!(f, "}}")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IreeVmfbFunction<'a> {
+    /// Local name for exports (`simple_mul`), full name for imports (`hal.buffer.assert`).
+    pub name: &'a str,
+    pub signature: IreeVmfbSignature<'a>,
+    /// Whether the module loads without this import. Always false for exports.
+    pub optional: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IreeVmfbDependency<'a> {
+    pub name: &'a str,
+    pub minimum_version: u32,
+    pub optional: bool,
+}
+
+/// How an executable embedded in a rodata segment is packaged.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum IreeVmfbExecutableContainer<'a> {
+    Elf,
+    Pe,
+    MachO,
+    /// A zip archive, e.g. a VMVX module, named after its first entry.
+    Archive(&'a str),
+    /// A FlatBuffer with this file identifier, e.g. `SPVE` for SPIR-V.
+    FlatBuffer(&'a str),
+}
+
+pub struct IreeVmfbExecutable<'a> {
+    /// Index into [`IreeVmfbModule::rodata_segments`].
+    pub segment: usize,
+    pub container: IreeVmfbExecutableContainer<'a>,
+    pub data: &'a [u8],
+}
+
+/// Difference between the interface of two versions of a module.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum IreeVmfbAbiChange {
+    ModuleRenamed {
+        old: String,
+        new: String,
+    },
+    ExportAdded(String),
+    ExportRemoved(String),
+    ExportChanged {
+        name: String,
+        old: String,
+        new: String,
+    },
+    ImportAdded(String),
+    ImportRemoved(String),
+    ImportChanged {
+        name: String,
+        old: String,
+        new: String,
+    },
+    DependencyAdded(String),
+    DependencyRemoved(String),
+}
+
+impl IreeVmfbAbiChange {
+    /// Whether callers or runtimes working with the old module may fail with the new one.
+    pub fn is_breaking(&self) -> bool {
+        !matches!(
+            self,
+            Self::ExportAdded(_) | Self::ImportRemoved(_) | Self::DependencyRemoved(_)
+        )
+    }
+}
+
+impl Display for IreeVmfbAbiChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::ModuleRenamed { old, new } => write!(f, "module renamed from {} to {}", old, new),
+            Self::ExportAdded(name) => write!(f, "export {} added", name),
+            Self::ExportRemoved(name) => write!(f, "export {} removed", name),
+            Self::ExportChanged { name, old, new } => {
+                write!(f, "export {} changed from {} to {}", name, old, new)
+            }
+            Self::ImportAdded(name) => write!(f, "import {} added", name),
+            Self::ImportRemoved(name) => write!(f, "import {} removed", name),
+            Self::ImportChanged { name, old, new } => {
+                write!(f, "import {} changed from {} to {}", name, old, new)
+            }
+            Self::DependencyAdded(name) => write!(f, "dependency {} added", name),
+            Self::DependencyRemoved(name) => write!(f, "dependency {} removed", name),
+        }
+    }
+}
+
+fn attrs<'a>(table: &Table<'a>, index: usize) -> Result<Vec<(&'a str, &'a str)>, IreeError> {
+    table
+        .tables(index)?
+        .iter()
+        .map(|attr| {
+            Ok((
+                attr.string(ATTR_KEY)?.unwrap_or_default(),
+                attr.string(ATTR_VALUE)?.unwrap_or_default(),
+            ))
+        })
+        .collect()
+}
+
+fn functions<'a>(root: &Table<'a>, index: usize) -> Result<Vec<IreeVmfbFunction<'a>>, IreeError> {
+    root.tables(index)?
+        .iter()
+        .map(|function| {
+            let signature = match function.table(FUNCTION_SIGNATURE)? {
+                Some(signature) => IreeVmfbSignature {
+                    calling_convention: signature
+                        .string(SIGNATURE_CALLING_CONVENTION)?
+                        .unwrap_or_default(),
+                    attrs: attrs(&signature, SIGNATURE_ATTRS)?,
+                },
+                None => IreeVmfbSignature::default(),
+            };
+            let optional = index == MODULE_IMPORTED_FUNCTIONS
+                && function.u32(IMPORT_FLAGS, FLAGS_REQUIRED)? & FLAGS_OPTIONAL != 0;
+            Ok(IreeVmfbFunction {
+                name: function.string(FUNCTION_NAME)?.unwrap_or_default(),
+                signature,
+                optional,
+            })
+        })
+        .collect()
+}
+
+fn executable_container(data: &[u8]) -> Option<IreeVmfbExecutableContainer<'_>> {
+    if data.starts_with(b"\x7fELF") {
+        Some(IreeVmfbExecutableContainer::Elf)
+    } else if data.starts_with(b"MZ") {
+        Some(IreeVmfbExecutableContainer::Pe)
+    } else if data.starts_with(b"\xcf\xfa\xed\xfe") {
+        Some(IreeVmfbExecutableContainer::MachO)
+    } else if data.starts_with(b"PK\x03\x04") {
+        let name_length = read_u16(data, 26).ok()? as usize;
+        let name = data.get(30..30 + name_length)?;
+        Some(IreeVmfbExecutableContainer::Archive(
+            std::str::from_utf8(name).ok()?,
+        ))
+    } else {
+        let identifier = data.get(4..8)?;
+        let plausible = identifier
+            .iter()
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+            && Table::root(data).is_ok();
+        plausible.then(|| {
+            IreeVmfbExecutableContainer::FlatBuffer(std::str::from_utf8(identifier).unwrap())
+        })
+    }
+}
+
+/// Changes of one kind of function between two modules, keyed by name.
+fn function_changes(
+    old: &[IreeVmfbFunction],
+    new: &[IreeVmfbFunction],
+    added: fn(String) -> IreeVmfbAbiChange,
+    removed: fn(String) -> IreeVmfbAbiChange,
+    changed: fn(String, String, String) -> IreeVmfbAbiChange,
+) -> Vec<IreeVmfbAbiChange> {
+    let mut changes = Vec::new();
+    for function in old.iter() {
+        match new.iter().find(|other| other.name == function.name) {
+            Some(other) if other.signature != function.signature => changes.push(changed(
+                function.name.to_string(),
+                function.signature.to_string(),
+                other.signature.to_string(),
+            )),
+            Some(_) => {}
+            None => changes.push(removed(function.name.to_string())),
+        }
+    }
+    for function in new.iter() {
+        // new optional imports don't break loading in older runtimes
+        if !function.optional && !old.iter().any(|other| other.name == function.name) {
+            changes.push(added(function.name.to_string()));
+        }
+    }
+    changes
+}
+
+impl<'a> IreeVmfbModule<'a> {
+    /// Reflection attributes of the module.
+    pub fn attrs(&self) -> Result<Vec<(&'a str, &'a str)>, IreeError> {
+        attrs(&self.root, MODULE_ATTRS)
+    }
+
+    /// Modules that must be registered before this one, e.g. `hal`.
+    pub fn dependencies(&self) -> Result<Vec<IreeVmfbDependency<'a>>, IreeError> {
+        self.root
+            .tables(MODULE_DEPENDENCIES)?
+            .iter()
+            .map(|dependency| {
+                Ok(IreeVmfbDependency {
+                    name: dependency.string(DEPENDENCY_NAME)?.unwrap_or_default(),
+                    minimum_version: dependency.u32(DEPENDENCY_MINIMUM_VERSION, 0)?,
+                    optional: dependency.u32(DEPENDENCY_FLAGS, FLAGS_REQUIRED)? & FLAGS_OPTIONAL
+                        != 0,
+                })
+            })
+            .collect()
+    }
+
+    pub fn exported_functions(&self) -> Result<Vec<IreeVmfbFunction<'a>>, IreeError> {
+        functions(&self.root, MODULE_EXPORTED_FUNCTIONS)
+    }
+
+    pub fn imported_functions(&self) -> Result<Vec<IreeVmfbFunction<'a>>, IreeError> {
+        functions(&self.root, MODULE_IMPORTED_FUNCTIONS)
+    }
+
+    /// Rodata segments holding compiled executables, recognized by their container format.
+    pub fn executables(&self) -> Result<Vec<IreeVmfbExecutable<'a>>, IreeError> {
+        Ok(self
+            .rodata_segments()?
+            .into_iter()
+            .enumerate()
+            .filter_map(|(segment, rodata)| {
+                Some(IreeVmfbExecutable {
+                    segment,
+                    container: executable_container(rodata.data)?,
+                    data: rodata.data,
+                })
+            })
+            .collect())
+    }
+
+    /// Changes to the module name, exports, required imports and dependencies from `old` to this
+    /// module, e.g. to catch ABI changes between model versions in CI.
+    pub fn abi_changes(&self, old: &IreeVmfbModule) -> Result<Vec<IreeVmfbAbiChange>, IreeError> {
+        let mut changes = Vec::new();
+        if old.name() != self.name() {
+            changes.push(IreeVmfbAbiChange::ModuleRenamed {
+                old: old.name().to_string(),
+                new: self.name().to_string(),
+            });
+        }
+        changes.extend(function_changes(
+            &old.exported_functions()?,
+            &self.exported_functions()?,
+            IreeVmfbAbiChange::ExportAdded,
+            IreeVmfbAbiChange::ExportRemoved,
+            |name, old, new| IreeVmfbAbiChange::ExportChanged { name, old, new },
+        ));
+        changes.extend(function_changes(
+            &old.imported_functions()?,
+            &self.imported_functions()?,
+            IreeVmfbAbiChange::ImportAdded,
+            IreeVmfbAbiChange::ImportRemoved,
+            |name, old, new| IreeVmfbAbiChange::ImportChanged { name, old, new },
+        ));
+
+        let old_dependencies = old.dependencies()?;
+        let new_dependencies = self.dependencies()?;
+        for dependency in old_dependencies.iter() {
+            if !new_dependencies.iter().any(|d| d.name == dependency.name) {
+                changes.push(IreeVmfbAbiChange::DependencyRemoved(
+                    dependency.name.to_string(),
+                ));
+            }
+        }
+        for dependency in new_dependencies.iter() {
+            if !dependency.optional && !old_dependencies.iter().any(|d| d.name == dependency.name) {
+                changes.push(IreeVmfbAbiChange::DependencyAdded(
+                    dependency.name.to_string(),
+                ));
+            }
+        }
+        Ok(changes)
+    }
+}
@@ -0,0 +1,7 @@
+//! Reading compiled modules (`.vmfb` files) in Rust, e.g. to check them before loading.
+
+mod flatbuffer;
+pub mod inspect;
+pub mod module;
+pub mod target;
+pub mod variants;
@@ -0,0 +1,117 @@
+use crate::err::IreeError;
+
+use super::flatbuffer::{invalid, read_u16, read_u32, Table};
+
+// Field indices in runtime/src/iree/schemas/bytecode_module_def.fbs at `IREE_REVISION`
+// (iree-3.1.0). Recheck them against the schema whenever `IREE_REVISION` is bumped;
+// tests/test_vmfb.rs compiles a module with known metadata to catch mismatches.
+
+// fields of `iree.vm.BytecodeModuleDef`
+const MODULE_NAME: usize = 0; // name: string
+const MODULE_VERSION: usize = 1; // version: uint32
+const MODULE_RODATA_SEGMENTS: usize = 7; // rodata_segments: [RodataSegmentDef]
+const MODULE_BYTECODE_VERSION: usize = 11; // bytecode_version: uint32
+
+// fields of `iree.vm.RodataSegmentDef`, after the `compression_type` union (type and value)
+const RODATA_EMBEDDED_DATA: usize = 2; // embedded_data: [uint8]
+const RODATA_EXTERNAL_DATA_OFFSET: usize = 3; // external_data_offset: uint64
+const RODATA_EXTERNAL_DATA_LENGTH: usize = 4; // external_data_length: uint64
+
+/// File identifier of bytecode module FlatBuffers.
+const FILE_IDENTIFIER: &[u8] = b"IREE";
+/// Archives are zip files whose first entry is the module FlatBuffer.
+const ZIP_LOCAL_FILE_HEADER: &[u8] = b"PK\x03\x04";
+/// Alignment of the external rodata following the FlatBuffer in an archive.
+const ARCHIVE_SEGMENT_ALIGNMENT: usize = 64;
+
+/// A compiled VM bytecode module (`.vmfb`), read without creating a runtime instance.
+pub struct IreeVmfbModule<'a> {
+    data: &'a [u8],
+    pub(super) root: Table<'a>,
+    /// offset of external rodata in `data`
+    rodata_offset: usize,
+    name: &'a str,
+}
+
+/// A read-only data segment, e.g. a string constant or an embedded executable.
+pub struct IreeVmfbRodataSegment<'a> {
+    pub data: &'a [u8],
+    /// Whether the segment is stored in the archive after the module rather than inside it.
+    pub external: bool,
+}
+
+impl<'a> IreeVmfbModule<'a> {
+    /// Parses a module, either a bare FlatBuffer or an archive with external rodata.
+    pub fn parse(data: &'a [u8]) -> Result<Self, IreeError> {
+        let (flatbuffer, rodata_offset) = if data.starts_with(ZIP_LOCAL_FILE_HEADER) {
+            let name_length = read_u16(data, 26)? as usize;
+            let extra_length = read_u16(data, 28)? as usize;
+            // the entry holds a size-prefixed FlatBuffer
+            let start = 30 + name_length + extra_length;
+            let length = read_u32(data, start)? as usize;
+            let end = (start + 4)
+                .checked_add(length)
+                .filter(|end| *end <= data.len())
+                .ok_or_else(|| invalid("module FlatBuffer out of bounds"))?;
+            let rodata_offset = end.next_multiple_of(ARCHIVE_SEGMENT_ALIGNMENT);
+            (&data[start + 4..end], rodata_offset)
+        } else {
+            (data, data.len())
+        };
+        if flatbuffer.get(4..8) != Some(FILE_IDENTIFIER) {
+            return Err(invalid("not an IREE bytecode module"));
+        }
+        let root = Table::root(flatbuffer)?;
+        let name = root
+            .string(MODULE_NAME)?
+            .ok_or_else(|| invalid("module has no name"))?;
+        Ok(Self {
+            data,
+            root,
+            rodata_offset,
+            name,
+        })
+    }
+
+    pub fn name(&self) -> &'a str {
+        self.name
+    }
+
+    /// Version of the module itself, as set by the compiler (usually 0).
+    pub fn version(&self) -> Result<u32, IreeError> {
+        self.root.u32(MODULE_VERSION, 0)
+    }
+
+    /// Bytecode format version as (major, minor).
+    pub fn bytecode_version(&self) -> Result<(u16, u16), IreeError> {
+        let version = self.root.u32(MODULE_BYTECODE_VERSION, 0)?;
+        Ok(((version >> 16) as u16, version as u16))
+    }
+
+    pub fn rodata_segments(&self) -> Result<Vec<IreeVmfbRodataSegment<'a>>, IreeError> {
+        self.root
+            .tables(MODULE_RODATA_SEGMENTS)?
+            .iter()
+            .map(|segment| {
+                if let Some(data) = segment.bytes(RODATA_EMBEDDED_DATA)? {
+                    return Ok(IreeVmfbRodataSegment {
+                        data,
+                        external: false,
+                    });
+                }
+                let offset = segment.u64(RODATA_EXTERNAL_DATA_OFFSET, 0)? as usize;
+                let length = segment.u64(RODATA_EXTERNAL_DATA_LENGTH, 0)? as usize;
+                let data = self
+                    .rodata_offset
+                    .checked_add(offset)
+                    .and_then(|start| Some(start..start.checked_add(length)?))
+                    .and_then(|range| self.data.get(range))
+                    .ok_or_else(|| invalid("external rodata out of bounds"))?;
+                Ok(IreeVmfbRodataSegment {
+                    data,
+                    external: true,
+                })
+            })
+            .collect()
+    }
+}
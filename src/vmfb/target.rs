@@ -0,0 +1,388 @@
+use std::fmt::Display;
+
+use crate::{
+    err::{IreeError, IreeErrorKind},
+    types::hal_device::IreeHalDevice,
+};
+
+use super::module::IreeVmfbModule;
+
+/// Prefixes of the executable formats HAL drivers load, as queried through `hal.executable.format`.
+const EXECUTABLE_FORMAT_PREFIXES: &[&str] = &[
+    "embedded-elf-",
+    "system-elf-",
+    "system-dll-",
+    "system-dylib-",
+    "vmvx-",
+    "vulkan-",
+    "cuda-",
+    "rocm-",
+    "metal-",
+    "webgpu-",
+];
+
+/// Formats whose executables are native code for one CPU architecture, named by their suffix.
+const CPU_EXECUTABLE_FORMAT_PREFIXES: &[&str] = &[
+    "embedded-elf-",
+    "system-elf-",
+    "system-dll-",
+    "system-dylib-",
+];
+
+/// CPU feature keys of the `hal.cpu` device query category.
+const CPU_FEATURES: &[&str] = &[
+    // x86_64
+    "sse3",
+    "ssse3",
+    "sse4.1",
+    "sse4.2",
+    "sse4a",
+    "avx",
+    "fma",
+    "f16c",
+    "avx2",
+    "avx512f",
+    "avx512cd",
+    "avx512vl",
+    "avx512dq",
+    "avx512bw",
+    "avx512ifma",
+    "avx512vbmi",
+    "avx512vpopcntdq",
+    "avx512vnni",
+    "avx512vbmi2",
+    "avx512bitalg",
+    "avx512bf16",
+    // arm_64
+    "dotprod",
+    "i8mm",
+    "fp16",
+    "bf16",
+    "sve",
+    "sve2",
+];
+
+/// Key of the device query category for CPU features.
+const CPU_QUERY_CATEGORY: &[u8] = b"hal.cpu";
+
+/// Architecture suffix of CPU executable formats for the host, e.g. `x86_64` in `embedded-elf-x86_64`.
+pub fn host_executable_arch() -> &'static str {
+    match std::env::consts::ARCH {
+        "x86_64" => "x86_64",
+        "x86" => "x86_32",
+        "aarch64" => "arm_64",
+        "arm" => "arm_32",
+        "riscv64" => "riscv_64",
+        "riscv32" => "riscv_32",
+        "wasm32" => "wasm_32",
+        arch => arch,
+    }
+}
+
+/// CPU features (as `hal.cpu` keys) the host supports.
+pub fn host_cpu_features() -> Vec<&'static str> {
+    #[allow(unused_mut)]
+    let mut features = Vec::new();
+    #[cfg(target_arch = "x86_64")]
+    {
+        macro_rules! detect {
+            ($($feature:tt),*) => {
+                $(if std::arch::is_x86_feature_detected!($feature) {
+                    features.push($feature);
+                })*
+            };
+        }
+        detect!(
+            "sse3",
+            "ssse3",
+            "sse4.1",
+            "sse4.2",
+            "sse4a",
+            "avx",
+            "fma",
+            "f16c",
+            "avx2",
+            "avx512f",
+            "avx512cd",
+            "avx512vl",
+            "avx512dq",
+            "avx512bw",
+            "avx512ifma",
+            "avx512vbmi",
+            "avx512vpopcntdq",
+            "avx512vnni",
+            "avx512vbmi2",
+            "avx512bitalg",
+            "avx512bf16"
+        );
+    }
+    #[cfg(target_arch = "aarch64")]
+    {
+        macro_rules! detect {
+            ($($feature:tt),*) => {
+                $(if std::arch::is_aarch64_feature_detected!($feature) {
+                    features.push($feature);
+                })*
+            };
+        }
+        detect!("dotprod", "i8mm", "fp16", "bf16", "sve", "sve2");
+    }
+    features
+}
+
+/// CPU features a module's native executables need.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum IreeVmfbCpuFeatures {
+    /// Features the module queries through `hal.cpu`, which compilers emit when executables are
+    /// specialized for them. Empty for modules without native executables.
+    Known(Vec<String>),
+    /// The module has native executables but doesn't tell what they were compiled for. The target
+    /// configuration (e.g. `--iree-llvmcpu-target-cpu-features`) isn't serialized into the module,
+    /// so they may use any feature.
+    Unknown,
+}
+
+impl Default for IreeVmfbCpuFeatures {
+    fn default() -> Self {
+        IreeVmfbCpuFeatures::Known(Vec::new())
+    }
+}
+
+impl IreeVmfbCpuFeatures {
+    /// The features if known.
+    pub fn known(&self) -> Option<&[String]> {
+        match self {
+            IreeVmfbCpuFeatures::Known(features) => Some(features),
+            IreeVmfbCpuFeatures::Unknown => None,
+        }
+    }
+}
+
+impl Display for IreeVmfbCpuFeatures {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IreeVmfbCpuFeatures::Known(features) => write!(f, "{}", features.join(", ")),
+            IreeVmfbCpuFeatures::Unknown => write!(f, "unknown"),
+        }
+    }
+}
+
+/// What a module needs from the device it runs on.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct IreeVmfbTargetInfo {
+    /// Formats of the embedded executables. The module runs on devices supporting any of them.
+    pub executable_formats: Vec<String>,
+    pub cpu_features: IreeVmfbCpuFeatures,
+}
+
+/// Why a module can't be loaded.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum IreeVmfbIncompatibility {
+    /// The runtime only loads modules with the same major and at most its minor bytecode version.
+    BytecodeVersion {
+        module: (u16, u16),
+        runtime: (u16, u16),
+    },
+    /// None of the module's executable formats can be loaded by `target` (a device or the host).
+    ExecutableFormat {
+        required: Vec<String>,
+        target: String,
+    },
+    MissingCpuFeatures(Vec<String>),
+    /// The module has native executables whose CPU features are unknown, so running them may crash
+    /// on an illegal instruction.
+    UnknownCpuFeatures,
+}
+
+impl Display for IreeVmfbIncompatibility {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IreeVmfbIncompatibility::BytecodeVersion { module, runtime } => write!(
+                f,
+                "bytecode version {}.{} is not supported by the runtime ({}.{})",
+                module.0, module.1, runtime.0, runtime.1
+            ),
+            IreeVmfbIncompatibility::ExecutableFormat { required, target } => write!(
+                f,
+                "none of the executable formats [{}] can be loaded on {}",
+                required.join(", "),
+                target
+            ),
+            IreeVmfbIncompatibility::MissingCpuFeatures(features) => {
+                write!(f, "missing CPU features [{}]", features.join(", "))
+            }
+            IreeVmfbIncompatibility::UnknownCpuFeatures => write!(
+                f,
+                "the CPU features of its native executables are unknown, declare them with \
+                 IreeVmfbVariants::variant_with_cpu_features"
+            ),
+        }
+    }
+}
+
+/// Bytecode version (major, minor) of the runtime iree-sys was built against.
+pub fn runtime_bytecode_version() -> (u16, u16) {
+    iree_sys::IREE_VM_BYTECODE_VERSION
+}
+
+fn is_executable_format(segment: &[u8]) -> bool {
+    segment.len() < 64
+        && EXECUTABLE_FORMAT_PREFIXES
+            .iter()
+            .any(|prefix| segment.starts_with(prefix.as_bytes()))
+        && segment
+            .iter()
+            .all(|c| c.is_ascii_alphanumeric() || *c == b'-' || *c == b'_')
+}
+
+/// Architecture of a CPU executable format, or `None` if it is not native code.
+pub(super) fn format_arch(format: &str) -> Option<&str> {
+    CPU_EXECUTABLE_FORMAT_PREFIXES
+        .iter()
+        .find_map(|prefix| format.strip_prefix(prefix))
+}
+
+impl<'a> IreeVmfbModule<'a> {
+    /// Reads the executable formats and CPU features from the string constants the module uses to
+    /// query the device. The CPU features are only known for modules dispatching on them through a
+    /// `hal.cpu` query, or without native executables; they are [`IreeVmfbCpuFeatures::Unknown`]
+    /// otherwise.
+    pub fn target_info(&self) -> Result<IreeVmfbTargetInfo, IreeError> {
+        let segments = self.rodata_segments()?;
+        let strings = segments
+            .iter()
+            .filter(|segment| !segment.external)
+            .map(|segment| segment.data)
+            .collect::<Vec<_>>();
+
+        let mut info = IreeVmfbTargetInfo::default();
+        for string in strings.iter() {
+            if is_executable_format(string) {
+                let format = String::from_utf8_lossy(string).to_string();
+                if !info.executable_formats.contains(&format) {
+                    info.executable_formats.push(format);
+                }
+            }
+        }
+        let native = info
+            .executable_formats
+            .iter()
+            .any(|format| format_arch(format).is_some());
+        if strings.contains(&CPU_QUERY_CATEGORY) {
+            let features = CPU_FEATURES
+                .iter()
+                .filter(|feature| strings.contains(&feature.as_bytes()))
+                .map(|feature| feature.to_string())
+                .collect();
+            info.cpu_features = IreeVmfbCpuFeatures::Known(features);
+        } else if native {
+            info.cpu_features = IreeVmfbCpuFeatures::Unknown;
+        }
+        Ok(info)
+    }
+
+    fn check_bytecode_version(
+        &self,
+        reasons: &mut Vec<IreeVmfbIncompatibility>,
+    ) -> Result<(), IreeError> {
+        let module = self.bytecode_version()?;
+        let runtime = runtime_bytecode_version();
+        if module.0 != runtime.0 || module.1 > runtime.1 {
+            reasons.push(IreeVmfbIncompatibility::BytecodeVersion { module, runtime });
+        }
+        Ok(())
+    }
+
+    fn incompatible(&self, reasons: Vec<IreeVmfbIncompatibility>) -> Result<(), IreeError> {
+        if reasons.is_empty() {
+            return Ok(());
+        }
+        Err(IreeError::new(IreeErrorKind::IncompatibleVmfb(
+            self.name().to_string(),
+            reasons,
+        )))
+    }
+
+    /// Reasons the module can't run on this host: an unsupported bytecode version, executables for
+    /// another CPU architecture only, or missing CPU features. Non-CPU executables (e.g. Vulkan) are
+    /// assumed to be loadable; use [`Self::device_incompatibilities`] to check them. Unknown CPU
+    /// features aren't reported, see [`IreeVmfbVariants`](super::variants::IreeVmfbVariants) for
+    /// picking between builds safely.
+    pub fn host_incompatibilities(&self) -> Result<Vec<IreeVmfbIncompatibility>, IreeError> {
+        let mut reasons = Vec::new();
+        self.check_bytecode_version(&mut reasons)?;
+
+        let info = self.target_info()?;
+        let arch = host_executable_arch();
+        let loadable = info.executable_formats.is_empty()
+            || info
+                .executable_formats
+                .iter()
+                .any(|format| format_arch(format).is_none_or(|format_arch| format_arch == arch));
+        if !loadable {
+            reasons.push(IreeVmfbIncompatibility::ExecutableFormat {
+                required: info.executable_formats.clone(),
+                target: format!("the host ({})", arch),
+            });
+        }
+
+        let host_features = host_cpu_features();
+        let missing = info
+            .cpu_features
+            .known()
+            .unwrap_or_default()
+            .iter()
+            .filter(|feature| !host_features.contains(&feature.as_str()))
+            .cloned()
+            .collect::<Vec<_>>();
+        if !missing.is_empty() {
+            reasons.push(IreeVmfbIncompatibility::MissingCpuFeatures(missing));
+        }
+        Ok(reasons)
+    }
+
+    /// Reasons the module can't run on `device`, asking the device which executable formats and CPU
+    /// features it supports. Like [`Self::host_incompatibilities`], unknown CPU features aren't
+    /// reported.
+    pub fn device_incompatibilities(
+        &self,
+        device: &IreeHalDevice,
+    ) -> Result<Vec<IreeVmfbIncompatibility>, IreeError> {
+        let mut reasons = Vec::new();
+        self.check_bytecode_version(&mut reasons)?;
+
+        let info = self.target_info()?;
+        let mut loadable = info.executable_formats.is_empty();
+        for format in info.executable_formats.iter() {
+            loadable |= device.supports_executable_format(format)?;
+        }
+        if !loadable {
+            reasons.push(IreeVmfbIncompatibility::ExecutableFormat {
+                required: info.executable_formats.clone(),
+                target: format!("device {}", device.id()),
+            });
+        }
+
+        let mut missing = Vec::new();
+        for feature in info.cpu_features.known().unwrap_or_default() {
+            // devices that don't know the feature (e.g. GPUs) don't have it
+            if !device.has_cpu_feature(feature).unwrap_or(false) {
+                missing.push(feature.clone());
+            }
+        }
+        if !missing.is_empty() {
+            reasons.push(IreeVmfbIncompatibility::MissingCpuFeatures(missing));
+        }
+        Ok(reasons)
+    }
+
+    /// Fails with `IreeErrorKind::IncompatibleVmfb` if the module can't run on this host.
+    pub fn check_host_compatibility(&self) -> Result<(), IreeError> {
+        self.incompatible(self.host_incompatibilities()?)
+    }
+
+    /// Fails with `IreeErrorKind::IncompatibleVmfb` if the module can't run on `device`.
+    pub fn check_device_compatibility(&self, device: &IreeHalDevice) -> Result<(), IreeError> {
+        self.incompatible(self.device_incompatibilities(device)?)
+    }
+}
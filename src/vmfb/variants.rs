@@ -0,0 +1,163 @@
+use crate::{
+    err::{IreeError, IreeErrorKind},
+    types::hal_device::IreeHalDevice,
+};
+
+use super::{
+    module::IreeVmfbModule,
+    target::{format_arch, host_cpu_features, IreeVmfbCpuFeatures, IreeVmfbIncompatibility},
+};
+
+/// One build of a model, e.g. compiled for AVX2.
+pub struct IreeVmfbVariant<'a> {
+    pub label: String,
+    pub data: &'a [u8],
+    /// CPU features the variant was compiled for, in addition to the ones the module queries, if
+    /// declared.
+    pub cpu_features: Option<Vec<String>>,
+}
+
+/// Builds of the same model for different targets, of which the best one for a host or device is
+/// picked: the one using the most CPU features among those with native executables, then any
+/// compatible one. Variants are told apart by the formats and CPU features detected in them (see
+/// [`IreeVmfbModule::target_info`]) and the ones declared with [`Self::variant_with_cpu_features`]; a
+/// tie between variants with identical requirements is an error rather than a guess, since one
+/// of them may use CPU features the host lacks. For the same reason, variants with native
+/// executables whose CPU features are unknown are only picked if the features are declared.
+#[derive(Default)]
+pub struct IreeVmfbVariants<'a> {
+    variants: Vec<IreeVmfbVariant<'a>>,
+}
+
+impl<'a> IreeVmfbVariants<'a> {
+    pub fn variant(&mut self, label: &str, data: &'a [u8]) -> &mut Self {
+        self.variants.push(IreeVmfbVariant {
+            label: label.to_string(),
+            data,
+            cpu_features: None,
+        });
+        self
+    }
+
+    /// Adds a variant compiled for `cpu_features` (as `hal.cpu` keys, e.g. `avx512f`), for modules
+    /// that don't record the features they were compiled for. Generic builds declare none.
+    pub fn variant_with_cpu_features(
+        &mut self,
+        label: &str,
+        data: &'a [u8],
+        cpu_features: &[&str],
+    ) -> &mut Self {
+        self.variants.push(IreeVmfbVariant {
+            label: label.to_string(),
+            data,
+            cpu_features: Some(cpu_features.iter().map(|f| f.to_string()).collect()),
+        });
+        self
+    }
+
+    pub fn variants(&self) -> &[IreeVmfbVariant<'a>] {
+        &self.variants
+    }
+
+    /// Picks the best variant this host can run.
+    pub fn select_for_host(&self) -> Result<&IreeVmfbVariant<'a>, IreeError> {
+        let host_features = host_cpu_features();
+        self.select(|module, cpu_features| {
+            let mut reasons = module.host_incompatibilities()?;
+            let missing = cpu_features
+                .iter()
+                .filter(|feature| !host_features.contains(&feature.as_str()))
+                .cloned()
+                .collect::<Vec<_>>();
+            if !missing.is_empty() {
+                reasons.push(IreeVmfbIncompatibility::MissingCpuFeatures(missing));
+            }
+            Ok(reasons)
+        })
+    }
+
+    /// Picks the best variant `device` can run.
+    pub fn select_for_device(
+        &self,
+        device: &IreeHalDevice,
+    ) -> Result<&IreeVmfbVariant<'a>, IreeError> {
+        self.select(|module, cpu_features| {
+            let mut reasons = module.device_incompatibilities(device)?;
+            let mut missing = Vec::new();
+            for feature in cpu_features.iter() {
+                if !device.has_cpu_feature(feature).unwrap_or(false) {
+                    missing.push(feature.clone());
+                }
+            }
+            if !missing.is_empty() {
+                reasons.push(IreeVmfbIncompatibility::MissingCpuFeatures(missing));
+            }
+            Ok(reasons)
+        })
+    }
+
+    /// `incompatibilities` gets each module and the CPU features declared for its variant.
+    fn select<F>(&self, incompatibilities: F) -> Result<&IreeVmfbVariant<'a>, IreeError>
+    where
+        F: Fn(&IreeVmfbModule, &[String]) -> Result<Vec<IreeVmfbIncompatibility>, IreeError>,
+    {
+        let mut compatible = Vec::new();
+        let mut all_reasons = Vec::new();
+        for variant in self.variants.iter() {
+            let module = IreeVmfbModule::parse(variant.data)?;
+            let declared = variant.cpu_features.as_deref().unwrap_or_default();
+            let mut reasons = incompatibilities(&module, declared)?;
+            let info = module.target_info()?;
+            if info.cpu_features == IreeVmfbCpuFeatures::Unknown && variant.cpu_features.is_none() {
+                reasons.push(IreeVmfbIncompatibility::UnknownCpuFeatures);
+            }
+            if !reasons.is_empty() {
+                for reason in reasons {
+                    if !all_reasons.contains(&reason) {
+                        all_reasons.push(reason);
+                    }
+                }
+                continue;
+            }
+
+            let native = info
+                .executable_formats
+                .iter()
+                .any(|format| format_arch(format).is_some());
+            let mut features = info.cpu_features.known().unwrap_or_default().to_vec();
+            for feature in declared.iter() {
+                if !features.contains(feature) {
+                    features.push(feature.clone());
+                }
+            }
+            features.sort();
+            let mut formats = info.executable_formats;
+            formats.sort();
+            compatible.push((variant, (native, features.len()), (formats, features)));
+        }
+
+        // the first variant with the highest score
+        let best = compatible.iter().rev().max_by_key(|(_, score, _)| *score);
+        match best {
+            Some((variant, _, requirements)) => {
+                let tied = compatible
+                    .iter()
+                    .filter(|(_, _, other)| other == requirements)
+                    .map(|(variant, _, _)| variant.label.clone())
+                    .collect::<Vec<_>>();
+                if tied.len() > 1 {
+                    return Err(IreeError::new(IreeErrorKind::AmbiguousVmfbVariants(tied)));
+                }
+                Ok(variant)
+            }
+            None => Err(IreeError::new(IreeErrorKind::IncompatibleVmfb(
+                self.variants
+                    .iter()
+                    .map(|variant| variant.label.as_str())
+                    .collect::<Vec<_>>()
+                    .join(" | "),
+                all_reasons,
+            ))),
+        }
+    }
+}
@@ -0,0 +1,94 @@
+//! Setup shared by the integration tests. Each test crate uses only some of it.
+#![allow(dead_code)]
+
+use std::{io::ErrorKind, process::Command};
+
+use iree_rs::types::{
+    allocator::IreeAllocator,
+    hal_buffer::{IreeHalBufferParams, IreeHalBufferViewParamsBuilder},
+    hal_device::IreeHalDevice,
+    runtime::{
+        instance::{IreeRuntimeInstance, IreeRuntimeInstanceOptionsBuilder},
+        session::{IreeRuntimeSession, IreeRuntimeSessionOptionsBuilder},
+    },
+};
+use iree_sys::iree::runtime::api::{
+    iree_hal_buffer_usage_bits_t_IREE_HAL_BUFFER_USAGE_DEFAULT,
+    iree_hal_memory_type_bits_t_IREE_HAL_MEMORY_TYPE_DEVICE_LOCAL,
+};
+
+/// An instance with all drivers, a `local-task` device and a session on it. The fields are dropped
+/// in declaration order, so the session goes before the device and instance it was created with.
+pub struct Runtime {
+    pub session: IreeRuntimeSession,
+    pub device: IreeHalDevice,
+    pub instance: IreeRuntimeInstance,
+    pub allocator: IreeAllocator,
+}
+
+impl Runtime {
+    pub fn local_task() -> Self {
+        let allocator = IreeAllocator::system_allocator();
+        let options = IreeRuntimeInstanceOptionsBuilder::default()
+            .use_all_available_drivers()
+            .build();
+        let instance = IreeRuntimeInstance::try_from_options(&options, &allocator).unwrap();
+        let device = instance.try_create_default_device("local-task").unwrap();
+        let session_options = IreeRuntimeSessionOptionsBuilder::default().build();
+        let session = IreeRuntimeSession::create_with_device(
+            &instance,
+            &session_options,
+            &device,
+            &allocator,
+        )
+        .unwrap();
+        Self {
+            session,
+            device,
+            instance,
+            allocator,
+        }
+    }
+}
+
+/// Parameters for device-local buffers with the default usage.
+pub fn device_local_params() -> IreeHalBufferParams {
+    IreeHalBufferViewParamsBuilder::default()
+        .type_(iree_hal_memory_type_bits_t_IREE_HAL_MEMORY_TYPE_DEVICE_LOCAL.0)
+        .usage(iree_hal_buffer_usage_bits_t_IREE_HAL_BUFFER_USAGE_DEFAULT.0)
+        .build()
+}
+
+/// Compiles `mlir` for the CPU with `iree-compile` from `IREE_COMPILE` or the `PATH`. Returns `None`
+/// after printing why when no compiler is set and `iree-compile` isn't installed, so that tests
+/// needing a freshly compiled module are skipped.
+pub fn compile_mlir(name: &str, mlir: &str) -> Option<Vec<u8>> {
+    let configured = std::env::var_os("IREE_COMPILE");
+    let compiler = configured.clone().unwrap_or_else(|| "iree-compile".into());
+    let dir = std::env::temp_dir().join(format!("iree-rs-compile-{}-{}", name, std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let source = dir.join(format!("{}.mlir", name));
+    let output = dir.join(format!("{}.vmfb", name));
+    std::fs::write(&source, mlir).unwrap();
+    let status = Command::new(&compiler)
+        .arg("--iree-hal-target-backends=llvm-cpu")
+        .arg(&source)
+        .arg("-o")
+        .arg(&output)
+        .status();
+    let status = match status {
+        Err(err) if err.kind() == ErrorKind::NotFound && configured.is_none() => {
+            eprintln!(
+                "skipping: iree-compile is not on the PATH, set IREE_COMPILE to compile {}",
+                name
+            );
+            std::fs::remove_dir_all(&dir).unwrap();
+            return None;
+        }
+        status => status.unwrap_or_else(|err| panic!("failed to run {:?}: {}", compiler, err)),
+    };
+    assert!(status.success(), "iree-compile failed on {}", name);
+    let data = std::fs::read(&output).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+    Some(data)
+}
@@ -1,3 +1,6 @@
+#[cfg(test)]
+mod common;
+
 #[cfg(test)]
 mod tests {
     use iree_rs::types::{
@@ -11,11 +14,15 @@ mod tests {
     };
     use iree_sys::iree::runtime::api::{
         iree_hal_buffer_usage_bits_t_IREE_HAL_BUFFER_USAGE_DEFAULT,
+        iree_hal_element_types_t_IREE_HAL_ELEMENT_TYPE_FLOAT_32,
         iree_hal_element_types_t_IREE_HAL_ELEMENT_TYPE_FLOAT_64,
+        iree_hal_element_types_t_IREE_HAL_ELEMENT_TYPE_INT_32,
         iree_hal_encoding_types_t_IREE_HAL_ENCODING_TYPE_DENSE_ROW_MAJOR,
         iree_hal_memory_type_bits_t_IREE_HAL_MEMORY_TYPE_DEVICE_LOCAL,
     };
 
+    use crate::common::{device_local_params, Runtime};
+
     #[test]
     fn test_hal_buffer_view() {
         let allocator = IreeAllocator::system_allocator();
@@ -52,4 +59,232 @@ mod tests {
         )
         .unwrap();
     }
+
+    #[test]
+    fn test_hal_allocator_statistics() {
+        let Runtime {
+            session,
+            device,
+            allocator,
+            ..
+        } = &Runtime::local_task();
+
+        let device_allocator = session.device_allocator();
+        let before = device_allocator.statistics();
+
+        let data = vec![0.0f64; 1 << 16];
+        let size = std::mem::size_of_val(data.as_slice()) as u64;
+        let byte_span = IreeConstByteSpan::from_slice(&data);
+        let buffer_params = device_local_params();
+        let buffer = IreeHalBufferView::allocate_buffer_copy(
+            device,
+            &device_allocator,
+            &vec![data.len()],
+            iree_hal_element_types_t_IREE_HAL_ELEMENT_TYPE_FLOAT_64,
+            iree_hal_encoding_types_t_IREE_HAL_ENCODING_TYPE_DENSE_ROW_MAJOR,
+            &buffer_params,
+            &byte_span,
+        )
+        .unwrap();
+        let in_use = device_allocator.statistics();
+
+        drop(buffer);
+        let after = device_allocator.statistics();
+        if in_use.device.bytes_allocated > before.device.bytes_allocated {
+            // statistics are tracked (IREE_STATISTICS_ENABLE)
+            assert!(in_use.device.bytes_live() >= before.device.bytes_live() + size);
+            assert!(in_use.device.bytes_peak >= before.device.bytes_live() + size);
+            assert!(after.device.bytes_live() + size <= in_use.device.bytes_live());
+        } else {
+            assert_eq!(in_use.device.bytes_allocated, 0);
+            assert_eq!(after.device.bytes_live(), 0);
+        }
+
+        let report = device_allocator.statistics_report(allocator).unwrap();
+        assert!(!report.is_empty());
+    }
+
+    #[test]
+    fn test_session_trim() {
+        let Runtime {
+            session, device, ..
+        } = &Runtime::local_task();
+        let device_allocator = session.device_allocator();
+
+        let data = (0..1 << 16).map(|i| i as f64).collect::<Vec<_>>();
+        let byte_span = IreeConstByteSpan::from_slice(&data);
+        let buffer_params = device_local_params();
+        let buffer = IreeHalBufferView::allocate_buffer_copy(
+            device,
+            &device_allocator,
+            &vec![data.len()],
+            iree_hal_element_types_t_IREE_HAL_ELEMENT_TYPE_FLOAT_64,
+            iree_hal_encoding_types_t_IREE_HAL_ENCODING_TYPE_DENSE_ROW_MAJOR,
+            &buffer_params,
+            &byte_span,
+        )
+        .unwrap();
+        let in_use = device_allocator.statistics();
+
+        // trimming only drops cached resources, so buffers the caller still holds stay allocated
+        // and keep their contents
+        session.trim().unwrap();
+        device_allocator.trim().unwrap();
+        let trimmed = device_allocator.statistics();
+        assert_eq!(trimmed.device.bytes_live(), in_use.device.bytes_live());
+        assert_eq!(buffer.to_vec::<f64>(device).unwrap(), data);
+
+        // the heap allocator of local devices doesn't pool, so freed buffers are released right
+        // away and trimming is a no-op
+        drop(buffer);
+        let freed = device_allocator.statistics();
+        device_allocator.trim().unwrap();
+        assert_eq!(device_allocator.statistics(), freed);
+    }
+
+    #[test]
+    fn test_hal_device_queries() {
+        let allocator = IreeAllocator::system_allocator();
+        let options = IreeRuntimeInstanceOptionsBuilder::default()
+            .use_all_available_drivers()
+            .build();
+        let instance = IreeRuntimeInstance::try_from_options(&options, &allocator).unwrap();
+        let drivers = instance.driver_names().unwrap();
+        assert!(drivers.iter().any(|driver| driver == "local-task"));
+        assert!(instance.try_create_default_device("not-a-driver").is_err());
+        let device = instance.try_create_default_device("local-task").unwrap();
+
+        assert!(!device.id().is_empty());
+        assert_eq!(device.driver(), "local-task");
+        assert!(!device
+            .supports_executable_format("not-a-real-format")
+            .unwrap());
+        #[cfg(all(target_arch = "x86_64", feature = "loader-embedded-elf"))]
+        assert!(device
+            .supports_executable_format("embedded-elf-x86_64")
+            .unwrap());
+        #[cfg(target_arch = "x86_64")]
+        device.has_cpu_feature("avx2").unwrap();
+        assert!(device.query_i64("not.a.category", "key").is_err());
+    }
+
+    #[test]
+    fn test_hal_buffer_view_elements() {
+        let Runtime {
+            session, device, ..
+        } = &Runtime::local_task();
+
+        let device_allocator = session.device_allocator();
+        let buffer_params = device_local_params();
+
+        let data = [1.0f32, 2.0, 3.0, 4.0];
+        let buffer = IreeHalBufferView::from_elements(
+            device,
+            &device_allocator,
+            &vec![2, 2],
+            &buffer_params,
+            &data,
+        )
+        .unwrap();
+        assert_eq!(
+            buffer.element_type(),
+            iree_hal_element_types_t_IREE_HAL_ELEMENT_TYPE_FLOAT_32
+        );
+        assert_eq!(buffer.to_vec::<f32>(device).unwrap(), data);
+        assert!(buffer.to_vec::<i32>(device).is_err());
+        assert_eq!(
+            buffer.try_to_string_as::<f32>(device, 16).unwrap(),
+            "2x2xf32=[1 2][3 4]"
+        );
+        assert_eq!(
+            buffer.try_to_string_as::<f32>(device, 3).unwrap(),
+            "2x2xf32=[1 2][3...]"
+        );
+
+        assert!(IreeHalBufferView::from_elements(
+            device,
+            &device_allocator,
+            &vec![3],
+            &buffer_params,
+            &data,
+        )
+        .is_err());
+
+        // compiled modules return signless integers, readable at either signedness
+        let data = [1i32, -2, 3];
+        let buffer = IreeHalBufferView::allocate_buffer_copy(
+            device,
+            &device_allocator,
+            &vec![data.len()],
+            iree_hal_element_types_t_IREE_HAL_ELEMENT_TYPE_INT_32,
+            iree_hal_encoding_types_t_IREE_HAL_ENCODING_TYPE_DENSE_ROW_MAJOR,
+            &buffer_params,
+            &IreeConstByteSpan::from_slice(&data),
+        )
+        .unwrap();
+        assert_eq!(buffer.to_vec::<i32>(device).unwrap(), data);
+        assert_eq!(buffer.to_vec::<u32>(device).unwrap()[0], 1);
+        assert!(buffer.to_vec::<i64>(device).is_err());
+        assert!(buffer.to_vec::<f32>(device).is_err());
+        assert_eq!(
+            buffer.try_to_string_as::<i32>(device, 16).unwrap(),
+            "3xi32=1 -2 3"
+        );
+    }
+
+    #[cfg(feature = "half")]
+    #[test]
+    fn test_hal_buffer_view_half_elements() {
+        use half::{bf16, f16};
+        use iree_sys::iree::runtime::api::{
+            iree_hal_element_types_t_IREE_HAL_ELEMENT_TYPE_BFLOAT_16,
+            iree_hal_element_types_t_IREE_HAL_ELEMENT_TYPE_FLOAT_16,
+        };
+
+        let Runtime {
+            session, device, ..
+        } = &Runtime::local_task();
+
+        let device_allocator = session.device_allocator();
+        let buffer_params = device_local_params();
+
+        let data = [1.0, 2.5, -3.0, 4.0].map(f16::from_f32);
+        let buffer = IreeHalBufferView::from_elements(
+            device,
+            &device_allocator,
+            &vec![4],
+            &buffer_params,
+            &data,
+        )
+        .unwrap();
+        assert_eq!(
+            buffer.element_type(),
+            iree_hal_element_types_t_IREE_HAL_ELEMENT_TYPE_FLOAT_16
+        );
+        assert_eq!(buffer.to_vec::<f16>(device).unwrap(), data);
+        assert!(buffer.to_vec::<bf16>(device).is_err());
+        assert_eq!(
+            buffer.try_to_string_as::<f16>(device, 16).unwrap(),
+            "4xf16=1 2.5 -3 4"
+        );
+
+        let data = [0.5, 1.0].map(bf16::from_f32);
+        let buffer = IreeHalBufferView::from_elements(
+            device,
+            &device_allocator,
+            &vec![2],
+            &buffer_params,
+            &data,
+        )
+        .unwrap();
+        assert_eq!(
+            buffer.element_type(),
+            iree_hal_element_types_t_IREE_HAL_ELEMENT_TYPE_BFLOAT_16
+        );
+        assert_eq!(buffer.to_vec::<bf16>(device).unwrap(), data);
+        assert_eq!(
+            buffer.try_to_string_as::<bf16>(device, 16).unwrap(),
+            "2xbf16=0.5 1"
+        );
+    }
 }
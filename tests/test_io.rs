@@ -0,0 +1,114 @@
+#[cfg(test)]
+mod common;
+
+#[cfg(all(test, feature = "io"))]
+mod tests {
+    use std::{io::Write, path::Path};
+
+    use iree_rs::types::{
+        allocator::IreeAllocator,
+        hal_buffer::{IreeHalBufferView, IreeHalBufferViewParamsBuilder},
+        io_parameters::{IreeIoParameterIndex, IreeIoParametersModuleBuilder},
+        runtime::{
+            call::IreeRuntimeCall,
+            instance::{IreeRuntimeInstance, IreeRuntimeInstanceOptionsBuilder},
+            session::{IreeRuntimeSession, IreeRuntimeSessionOptionsBuilder},
+        },
+        vm_module::IreeVmModule,
+    };
+    use iree_sys::iree::runtime::api::{
+        iree_hal_buffer_usage_bits_t_IREE_HAL_BUFFER_USAGE_DEFAULT,
+        iree_hal_memory_type_bits_t_IREE_HAL_MEMORY_TYPE_DEVICE_LOCAL, iree_runtime_call_flags_t,
+    };
+
+    use crate::common::compile_mlir;
+
+    /// Multiplies its input by the externalized parameter `model::weight`.
+    const SCALE_MLIR: &str = r#"
+util.global private @weight = #stream.parameter.named<"model"::"weight"> : tensor<4xf32>
+
+func.func @scale(%input: tensor<4xf32>) -> tensor<4xf32> {
+  %weight = util.global.load @weight : tensor<4xf32>
+  %result = arith.mulf %input, %weight : tensor<4xf32>
+  return %result : tensor<4xf32>
+}
+"#;
+
+    /// Writes a safetensors file with a single `weight` of four f32s.
+    fn write_safetensors(path: &Path) {
+        let header = br#"{"weight":{"dtype":"F32","shape":[4],"data_offsets":[0,16]}}"#;
+        let mut file = std::fs::File::create(path).unwrap();
+        file.write_all(&(header.len() as u64).to_le_bytes())
+            .unwrap();
+        file.write_all(header).unwrap();
+        for value in [1.0f32, 2.0, 3.0, 4.0] {
+            file.write_all(&value.to_le_bytes()).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_parameters_module() {
+        let Some(bytecode) = compile_mlir("scale", SCALE_MLIR) else {
+            return;
+        };
+
+        let allocator = IreeAllocator::system_allocator();
+        let options = IreeRuntimeInstanceOptionsBuilder::default()
+            .use_all_available_drivers()
+            .build();
+        let instance = IreeRuntimeInstance::try_from_options(&options, &allocator).unwrap();
+        let device = instance.try_create_default_device("local-task").unwrap();
+
+        let dir = std::env::temp_dir().join(format!("iree-rs-test-io-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("parameters.safetensors");
+        write_safetensors(&path);
+        let mut index = IreeIoParameterIndex::new(&instance).unwrap();
+        assert!(index.is_empty());
+        index.append_file(&path).unwrap();
+        assert_eq!(index.len(), 1);
+        assert!(index.append_file(dir.join("missing.safetensors")).is_err());
+
+        let parameters = IreeIoParametersModuleBuilder::default()
+            .scope("model", &index)
+            .build(&instance)
+            .unwrap();
+        assert_eq!(parameters.name(), "io_parameters");
+        let module = IreeVmModule::from_bytecode(&instance, &bytecode).unwrap();
+
+        let session_options = IreeRuntimeSessionOptionsBuilder::default().build();
+        let session = IreeRuntimeSession::create_with_device(
+            &instance,
+            &session_options,
+            &device,
+            &allocator,
+        )
+        .unwrap();
+        session.append_module(&parameters).unwrap();
+        session.append_module(&module).unwrap();
+        // the index maps the file, so the model doesn't need it on disk any more
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let buffer_params = IreeHalBufferViewParamsBuilder::default()
+            .type_(iree_hal_memory_type_bits_t_IREE_HAL_MEMORY_TYPE_DEVICE_LOCAL.0)
+            .usage(iree_hal_buffer_usage_bits_t_IREE_HAL_BUFFER_USAGE_DEFAULT.0)
+            .build();
+        let input = IreeHalBufferView::from_elements(
+            &device,
+            &session.device_allocator(),
+            &vec![4],
+            &buffer_params,
+            &[2.0f32, 2.0, 2.0, 2.0],
+        )
+        .unwrap();
+        let function = session.lookup_function("module.scale").unwrap();
+        let mut call = IreeRuntimeCall::initialize(&session, &function).unwrap();
+        call.inputs_push_back_buffer_view(&input).unwrap();
+        call.invoke(iree_runtime_call_flags_t::default()).unwrap();
+        let output = call.outputs_pop_front_buffer_view().unwrap();
+        assert_eq!(
+            output.to_vec::<f32>(&device).unwrap(),
+            vec![2.0, 4.0, 6.0, 8.0]
+        );
+    }
+}
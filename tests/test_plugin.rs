@@ -0,0 +1,151 @@
+#[cfg(test)]
+mod common;
+
+#[cfg(all(
+    test,
+    feature = "driver-local-sync",
+    feature = "loader-embedded-elf",
+    feature = "loader-system-library"
+))]
+mod tests {
+    use std::path::PathBuf;
+
+    use iree_rs::types::{
+        allocator::IreeAllocator,
+        hal_buffer::{IreeHalBufferView, IreeHalBufferViewParamsBuilder},
+        hal_device::{IreeHalExecutableLoader, IreeHalLocalDeviceBuilder},
+        hal_executable_plugin::IreeHalExecutablePlugin,
+        runtime::{
+            call::IreeRuntimeCall,
+            instance::{IreeRuntimeInstance, IreeRuntimeInstanceOptionsBuilder},
+            session::{IreeRuntimeSession, IreeRuntimeSessionOptionsBuilder},
+        },
+        vm_module::IreeVmModule,
+    };
+    use iree_sys::iree::runtime::api::{
+        iree_hal_buffer_usage_bits_t_IREE_HAL_BUFFER_USAGE_DEFAULT,
+        iree_hal_memory_type_bits_t_IREE_HAL_MEMORY_TYPE_DEVICE_LOCAL, iree_runtime_call_flags_t,
+    };
+
+    // keep the sample plugin crate linked so cargo builds its cdylib next to the test binary
+    #[allow(unused_imports)]
+    use iree_rs_sample_plugin::iree_hal_executable_plugin_query;
+
+    /// The sample plugin's shared library lives next to the test binary in `target/<profile>/deps`.
+    fn sample_plugin_path() -> PathBuf {
+        std::env::current_exe()
+            .unwrap()
+            .parent()
+            .unwrap()
+            .join(format!(
+                "{}iree_rs_sample_plugin{}",
+                std::env::consts::DLL_PREFIX,
+                std::env::consts::DLL_SUFFIX
+            ))
+    }
+
+    #[test]
+    fn test_system_library_plugin() {
+        let allocator = IreeAllocator::system_allocator();
+        let options = IreeRuntimeInstanceOptionsBuilder::default()
+            .use_all_available_drivers()
+            .build();
+        let instance = IreeRuntimeInstance::try_from_options(&options, &allocator).unwrap();
+
+        let plugin = IreeHalExecutablePlugin::load_system_library(
+            sample_plugin_path().to_str().unwrap(),
+            &[],
+            &instance.host_allocator(),
+        )
+        .unwrap();
+
+        let device = IreeHalLocalDeviceBuilder::default()
+            .loader(IreeHalExecutableLoader::SystemLibrary)
+            .loader(IreeHalExecutableLoader::EmbeddedElf)
+            .executable_plugin(&plugin)
+            .build(&instance)
+            .unwrap();
+
+        let session_options = IreeRuntimeSessionOptionsBuilder::default().build();
+        IreeRuntimeSession::create_with_device(&instance, &session_options, &device, &allocator)
+            .unwrap();
+    }
+
+    #[cfg(all(target_arch = "x86_64", feature = "loader-embedded-elf"))]
+    #[test]
+    fn test_plugin_dispatch() {
+        use crate::common::compile_mlir;
+
+        // the executable imports the plugin's kernel
+        let Some(bytecode) = compile_mlir(
+            "plugin_simple_mul",
+            include_str!("../plugins/sample-plugin/simple_mul.mlir"),
+        ) else {
+            return;
+        };
+
+        let allocator = IreeAllocator::system_allocator();
+        let options = IreeRuntimeInstanceOptionsBuilder::default()
+            .use_all_available_drivers()
+            .build();
+        let instance = IreeRuntimeInstance::try_from_options(&options, &allocator).unwrap();
+
+        let plugin = IreeHalExecutablePlugin::load_system_library(
+            sample_plugin_path().to_str().unwrap(),
+            &[],
+            &instance.host_allocator(),
+        )
+        .unwrap();
+        let device = IreeHalLocalDeviceBuilder::default()
+            .loader(IreeHalExecutableLoader::EmbeddedElf)
+            .executable_plugin(&plugin)
+            .build(&instance)
+            .unwrap();
+        let session_options = IreeRuntimeSessionOptionsBuilder::default().build();
+        let session = IreeRuntimeSession::create_with_device(
+            &instance,
+            &session_options,
+            &device,
+            &allocator,
+        )
+        .unwrap();
+        let module = IreeVmModule::from_bytecode(&instance, &bytecode).unwrap();
+        session.append_module(&module).unwrap();
+
+        // more than one workgroup of 64 elements
+        let lhs = (0..100).map(|i| i as f32).collect::<Vec<_>>();
+        let rhs = vec![2.0f32; lhs.len()];
+        let params = IreeHalBufferViewParamsBuilder::default()
+            .type_(iree_hal_memory_type_bits_t_IREE_HAL_MEMORY_TYPE_DEVICE_LOCAL.0)
+            .usage(iree_hal_buffer_usage_bits_t_IREE_HAL_BUFFER_USAGE_DEFAULT.0)
+            .build();
+        let device_allocator = session.device_allocator();
+        let shape = vec![lhs.len()];
+        let lhs_view =
+            IreeHalBufferView::from_elements(&device, &device_allocator, &shape, &params, &lhs)
+                .unwrap();
+        let rhs_view =
+            IreeHalBufferView::from_elements(&device, &device_allocator, &shape, &params, &rhs)
+                .unwrap();
+
+        let function = session.lookup_function("plugin.simple_mul").unwrap();
+        let mut call = IreeRuntimeCall::initialize(&session, &function).unwrap();
+        call.inputs_push_back_buffer_view(&lhs_view).unwrap();
+        call.inputs_push_back_buffer_view(&rhs_view).unwrap();
+        call.invoke(iree_runtime_call_flags_t::default()).unwrap();
+        let output = call.outputs_pop_front_buffer_view().unwrap();
+        assert_eq!(
+            output.to_vec::<f32>(&device).unwrap(),
+            lhs.iter().map(|x| x * 2.0).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_missing_plugin() {
+        let allocator = IreeAllocator::system_allocator();
+        assert!(
+            IreeHalExecutablePlugin::load_system_library("does-not-exist.so", &[], &allocator)
+                .is_err()
+        );
+    }
+}
@@ -1,12 +1,51 @@
+#[cfg(test)]
+mod common;
+
 #[cfg(test)]
 mod tests {
-    use iree_rs::types::{
-        allocator::IreeAllocator,
-        runtime::{
-            instance::{IreeRuntimeInstance, IreeRuntimeInstanceOptionsBuilder},
-            session::{IreeRuntimeSession, IreeRuntimeSessionOptionsBuilder},
+    use std::{
+        sync::{Arc, Barrier, Mutex},
+        time::Duration,
+    };
+
+    use iree_rs::{
+        err::IreeErrorKind,
+        types::{
+            allocator::IreeAllocator,
+            bytespan::IreeConstByteSpan,
+            hal_buffer::IreeHalBufferView,
+            runtime::{
+                batch::{IreeBatchOptionsBuilder, IreeBatchTensor, IreeBatchWorker},
+                call::IreeRuntimeCall,
+                instance::{IreeRuntimeInstance, IreeRuntimeInstanceOptionsBuilder},
+                session::{IreeRuntimeSession, IreeRuntimeSessionOptionsBuilder},
+                signature::{IreeArgumentType, IreeFunctionSignature},
+            },
+            vm_module::IreeVmModule,
+            vm_native_module::IreeVmNativeModuleBuilder,
+            vm_value::{IreeVmValue, IreeVmValueType},
         },
     };
+    use iree_sys::iree::runtime::api::{
+        iree_hal_element_types_t_IREE_HAL_ELEMENT_TYPE_FLOAT_32,
+        iree_hal_element_types_t_IREE_HAL_ELEMENT_TYPE_INT_32,
+        iree_hal_encoding_types_t_IREE_HAL_ENCODING_TYPE_DENSE_ROW_MAJOR,
+        iree_runtime_call_flags_t,
+    };
+
+    use crate::common::{compile_mlir, device_local_params, Runtime};
+
+    // borrowed by the sessions, so it must outlive them
+    static SIMPLE_MUL_VMFB: &[u8] = include_bytes!("../iree-sys/examples/simple_mul_module.vmfb");
+
+    /// `simple_mul` again, compiled at test time so it carries the reflection attributes (the
+    /// checked-in module predates them) that tensor arguments are checked against.
+    const SIMPLE_MUL_MLIR: &str = r#"
+func.func @simple_mul(%arg0: tensor<4xf32>, %arg1: tensor<4xf32>) -> tensor<4xf32> {
+  %0 = arith.mulf %arg0, %arg1 : tensor<4xf32>
+  return %0 : tensor<4xf32>
+}
+"#;
 
     #[test]
     fn test_runtime_instance() {
@@ -44,4 +83,338 @@ mod tests {
         )
         .unwrap();
     }
+
+    #[test]
+    fn test_runtime_call_reset() {
+        let Runtime {
+            session, device, ..
+        } = &Runtime::local_task();
+        session
+            .append_bytecode_module_from_memory(SIMPLE_MUL_VMFB, &IreeAllocator::null())
+            .unwrap();
+
+        let data = [1.0f32, 2.0, 3.0, 4.0];
+        let byte_span = IreeConstByteSpan::from_slice(&data);
+        let buffer_params = device_local_params();
+        let input = IreeHalBufferView::allocate_buffer_copy(
+            device,
+            &session.device_allocator(),
+            &vec![data.len()],
+            iree_hal_element_types_t_IREE_HAL_ELEMENT_TYPE_FLOAT_32,
+            iree_hal_encoding_types_t_IREE_HAL_ENCODING_TYPE_DENSE_ROW_MAJOR,
+            &buffer_params,
+            &byte_span,
+        )
+        .unwrap();
+
+        let function = session.lookup_function("module.simple_mul").unwrap();
+        assert_eq!(function.name(), "module.simple_mul");
+        assert!(session.lookup_function("module.missing").is_err());
+
+        let mut call = IreeRuntimeCall::initialize(session, &function).unwrap();
+        for _ in 0..3 {
+            call.inputs_push_back_buffer_view(&input).unwrap();
+            call.inputs_push_back_buffer_view(&input).unwrap();
+            call.invoke(iree_runtime_call_flags_t::default()).unwrap();
+            let output = call.outputs_pop_front_buffer_view().unwrap();
+            assert_eq!(output.shape().unwrap(), vec![4]);
+            call.reset();
+        }
+
+        assert_eq!(call.function_name(), "module.simple_mul");
+    }
+
+    #[test]
+    fn test_runtime_call_rejects_other_session() {
+        let Runtime { session, .. } = &Runtime::local_task();
+        let Runtime { session: other, .. } = &Runtime::local_task();
+        for session in [session, other] {
+            session
+                .append_bytecode_module_from_memory(SIMPLE_MUL_VMFB, &IreeAllocator::null())
+                .unwrap();
+        }
+
+        let function = session.lookup_function("module.simple_mul").unwrap();
+        let err = IreeRuntimeCall::initialize(other, &function).err().unwrap();
+        assert!(
+            matches!(err.kind(), IreeErrorKind::InvalidInput(name, _) if name == "module.simple_mul")
+        );
+        IreeRuntimeCall::initialize(session, &function).unwrap();
+    }
+
+    #[test]
+    fn test_function_signature_parse() {
+        let signature = IreeFunctionSignature::parse(
+            "0rri_r",
+            &[(
+                "iree.abi.declaration",
+                "sync func @main(%input0: tensor<4x?xf32>, %input1: tensor<2xi32>, %input2: i32) -> (%output0: tensor<4xf32>)",
+            )],
+        )
+        .unwrap();
+        assert_eq!(
+            signature.arguments[0],
+            IreeArgumentType::Tensor {
+                dtype: "f32".to_string(),
+                shape: vec![Some(4), None],
+            }
+        );
+        assert_eq!(signature.arguments[1].to_string(), "tensor<2xi32>");
+        assert_eq!(signature.arguments[2].to_string(), "i32");
+        assert_eq!(signature.results[0].to_string(), "tensor<4xf32>");
+
+        let signature = IreeFunctionSignature::parse(
+            "0r_r",
+            &[(
+                "iree.abi",
+                r#"{"a":[["ndarray","f32",2,null,3]],"r":[["ndarray","f32",1,3]],"v":1}"#,
+            )],
+        )
+        .unwrap();
+        assert_eq!(signature.arguments[0].to_string(), "tensor<?x3xf32>");
+
+        // variadic segments aren't checked
+        assert!(IreeFunctionSignature::parse("0CrD_r", &[]).is_none());
+    }
+
+    #[test]
+    fn test_runtime_call_validates_inputs() {
+        let Runtime {
+            session, device, ..
+        } = &Runtime::local_task();
+        session
+            .append_bytecode_module_from_memory(SIMPLE_MUL_VMFB, &IreeAllocator::null())
+            .unwrap();
+
+        let data = [1.0f32, 2.0, 3.0, 4.0];
+        let byte_span = IreeConstByteSpan::from_slice(&data);
+        let buffer_params = device_local_params();
+        let input = IreeHalBufferView::allocate_buffer_copy(
+            device,
+            &session.device_allocator(),
+            &vec![data.len()],
+            iree_hal_element_types_t_IREE_HAL_ELEMENT_TYPE_FLOAT_32,
+            iree_hal_encoding_types_t_IREE_HAL_ENCODING_TYPE_DENSE_ROW_MAJOR,
+            &buffer_params,
+            &byte_span,
+        )
+        .unwrap();
+        assert_eq!(
+            input.element_type(),
+            iree_hal_element_types_t_IREE_HAL_ELEMENT_TYPE_FLOAT_32
+        );
+
+        let function = session.lookup_function("module.simple_mul").unwrap();
+        let signature = function.signature().unwrap();
+        assert_eq!(signature.arguments.len(), 2);
+        assert_eq!(signature.results.len(), 1);
+
+        let mut call = IreeRuntimeCall::initialize(session, &function).unwrap();
+        call.inputs_push_back_buffer_view(&input).unwrap();
+        let err = call
+            .invoke(iree_runtime_call_flags_t::default())
+            .unwrap_err();
+        assert!(matches!(err.kind(), IreeErrorKind::InvalidInput(..)));
+        assert!(err.to_string().contains("expected 2 arguments"));
+
+        call.inputs_push_back_buffer_view(&input).unwrap();
+        let err = call.inputs_push_back_buffer_view(&input).unwrap_err();
+        assert!(err.to_string().contains("argument 2"));
+        call.invoke(iree_runtime_call_flags_t::default()).unwrap();
+    }
+
+    #[test]
+    fn test_runtime_call_rejects_wrong_tensor() {
+        let Some(bytecode) = compile_mlir("simple_mul", SIMPLE_MUL_MLIR) else {
+            return;
+        };
+
+        let Runtime {
+            session,
+            device,
+            instance,
+            ..
+        } = &Runtime::local_task();
+        let module = IreeVmModule::from_bytecode(instance, &bytecode).unwrap();
+        session.append_module(&module).unwrap();
+
+        let function = session.lookup_function("module.simple_mul").unwrap();
+        assert_eq!(
+            function.signature().unwrap().arguments[0].to_string(),
+            "tensor<4xf32>"
+        );
+
+        let buffer_params = device_local_params();
+        let data = [1i32, 2, 3];
+        let input = IreeHalBufferView::allocate_buffer_copy(
+            device,
+            &session.device_allocator(),
+            &vec![data.len()],
+            iree_hal_element_types_t_IREE_HAL_ELEMENT_TYPE_INT_32,
+            iree_hal_encoding_types_t_IREE_HAL_ENCODING_TYPE_DENSE_ROW_MAJOR,
+            &buffer_params,
+            &IreeConstByteSpan::from_slice(&data),
+        )
+        .unwrap();
+
+        let mut call = IreeRuntimeCall::initialize(session, &function).unwrap();
+        let err = call.inputs_push_back_buffer_view(&input).unwrap_err();
+        assert!(matches!(err.kind(), IreeErrorKind::InvalidInput(..)));
+        let message = err.to_string();
+        assert!(
+            message.contains("argument 0: expected tensor<4xf32>, got tensor<3xi32>"),
+            "{}",
+            message
+        );
+    }
+
+    #[test]
+    fn test_batch_worker() {
+        let runtime = Runtime::local_task();
+        runtime
+            .session
+            .append_bytecode_module_from_memory(SIMPLE_MUL_VMFB, &IreeAllocator::null())
+            .unwrap();
+
+        // simple_mul takes tensor<4xf32>, so samples are scalars in a batch of 4
+        let batch_options = IreeBatchOptionsBuilder::default()
+            .max_batch_size(4)
+            .timeout(Duration::from_millis(100))
+            .pad_to_max_batch_size()
+            .build();
+        let missing = Runtime::local_task();
+        assert!(IreeBatchWorker::spawn(
+            missing.session,
+            missing.device,
+            "module.missing",
+            &batch_options
+        )
+        .is_err());
+        let worker = IreeBatchWorker::spawn(
+            runtime.session,
+            runtime.device,
+            "module.simple_mul",
+            &batch_options,
+        )
+        .unwrap();
+
+        let scalar = |value: f32| IreeBatchTensor::from_slice(&[], &[value]);
+        std::thread::scope(|scope| {
+            let handles = (1..=3)
+                .map(|i| {
+                    let worker = &worker;
+                    scope.spawn(move || {
+                        let x = i as f32;
+                        let outputs = worker.infer(vec![scalar(x), scalar(x + 1.0)]).unwrap();
+                        assert_eq!(outputs.len(), 1);
+                        assert_eq!(outputs[0], scalar(x * (x + 1.0)));
+                    })
+                })
+                .collect::<Vec<_>>();
+            for handle in handles {
+                handle.join().unwrap();
+            }
+        });
+
+        // a sample that doesn't fit the function fails on its own
+        assert!(worker.infer(vec![scalar(1.0)]).is_err());
+        assert!(worker.infer(vec![scalar(2.0), scalar(3.0)]).is_ok());
+    }
+
+    #[test]
+    fn test_batch_worker_batches_concurrent_requests() {
+        let runtime = Runtime::local_task();
+
+        // echoes its input and records the leading dimension of every invocation
+        let batch_sizes = Arc::new(Mutex::new(Vec::new()));
+        let recorded = batch_sizes.clone();
+        let module = IreeVmNativeModuleBuilder::<()>::new("batch")
+            .function(
+                "echo",
+                &[IreeVmValueType::Ref],
+                &[IreeVmValueType::Ref],
+                move |_, mut args| {
+                    if let IreeVmValue::BufferView(input) = &args[0] {
+                        recorded.lock().unwrap().push(input.shape()?[0]);
+                    }
+                    Ok(vec![args.remove(0)])
+                },
+            )
+            .build(&runtime.instance)
+            .unwrap();
+        runtime.session.append_module(&module).unwrap();
+
+        // the batch is full with the third request, long before the timeout
+        let batch_options = IreeBatchOptionsBuilder::default()
+            .max_batch_size(3)
+            .timeout(Duration::from_secs(10))
+            .build();
+        let worker = IreeBatchWorker::spawn(
+            runtime.session,
+            runtime.device,
+            "batch.echo",
+            &batch_options,
+        )
+        .unwrap();
+
+        let sample = |value: f32| IreeBatchTensor::from_slice(&[2], &[value, -value]);
+        let barrier = Barrier::new(3);
+        std::thread::scope(|scope| {
+            let handles = (1..=3)
+                .map(|i| {
+                    let (worker, barrier) = (&worker, &barrier);
+                    scope.spawn(move || {
+                        barrier.wait();
+                        let outputs = worker.infer(vec![sample(i as f32)]).unwrap();
+                        assert_eq!(outputs, vec![sample(i as f32)]);
+                    })
+                })
+                .collect::<Vec<_>>();
+            for handle in handles {
+                handle.join().unwrap();
+            }
+        });
+        assert_eq!(*batch_sizes.lock().unwrap(), vec![3]);
+    }
+
+    #[test]
+    fn test_batch_worker_rejects_mismatched_sample() {
+        let runtime = Runtime::local_task();
+        let module = IreeVmNativeModuleBuilder::<()>::new("batch")
+            .function(
+                "echo",
+                &[IreeVmValueType::Ref],
+                &[IreeVmValueType::Ref],
+                |_, mut args| Ok(vec![args.remove(0)]),
+            )
+            .build(&runtime.instance)
+            .unwrap();
+        runtime.session.append_module(&module).unwrap();
+
+        // both samples always end up in one batch, so whichever comes second is rejected
+        let batch_options = IreeBatchOptionsBuilder::default()
+            .max_batch_size(2)
+            .timeout(Duration::from_secs(10))
+            .build();
+        let worker = IreeBatchWorker::spawn(
+            runtime.session,
+            runtime.device,
+            "batch.echo",
+            &batch_options,
+        )
+        .unwrap();
+
+        let results = std::thread::scope(|scope| {
+            let handles = [vec![1.0f32, 2.0], vec![1.0f32, 2.0, 3.0]].map(|data| {
+                let worker = &worker;
+                scope.spawn(move || {
+                    worker.infer(vec![IreeBatchTensor::from_slice(&[data.len()], &data)])
+                })
+            });
+            handles.map(|handle| handle.join().unwrap())
+        });
+        assert_eq!(results.iter().filter(|result| result.is_ok()).count(), 1);
+        let err = results.into_iter().find_map(Result::err).unwrap();
+        assert!(matches!(err.kind(), IreeErrorKind::InvalidInput(name, _) if name == "batch.echo"));
+    }
 }
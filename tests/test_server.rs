@@ -0,0 +1,147 @@
+#[cfg(all(test, feature = "server"))]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader, Read, Write},
+        net::TcpStream,
+        process::{Child, Command, Stdio},
+    };
+
+    use serde_json::{json, Value};
+
+    /// Kills the server when a test ends, also on failure.
+    struct ServerProcess(Child);
+
+    impl Drop for ServerProcess {
+        fn drop(&mut self) {
+            let _ = self.0.kill();
+            let _ = self.0.wait();
+        }
+    }
+
+    /// Starts the server on a free port and returns its address once it is listening.
+    fn start_server() -> (ServerProcess, String) {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_iree-server"))
+            .args([
+                "--address",
+                "127.0.0.1:0",
+                concat!(
+                    env!("CARGO_MANIFEST_DIR"),
+                    "/iree-sys/examples/simple_mul_module.vmfb"
+                ),
+            ])
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        // the address is the last line printed, after the models are loaded
+        let address = BufReader::new(child.stdout.take().unwrap())
+            .lines()
+            .find_map(|line| {
+                line.unwrap()
+                    .strip_prefix("listening on http://")
+                    .map(str::to_string)
+            })
+            .unwrap();
+        (ServerProcess(child), address)
+    }
+
+    fn request(address: &str, method: &str, path: &str, body: Option<Value>) -> (u16, Value) {
+        let body = body.map(|body| body.to_string()).unwrap_or_default();
+        let mut stream = TcpStream::connect(address).unwrap();
+        write!(
+            stream,
+            "{} {} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+            method,
+            path,
+            address,
+            body.len(),
+            body
+        )
+        .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        let status = head.split(' ').nth(1).unwrap().parse().unwrap();
+        (status, serde_json::from_str(body).unwrap())
+    }
+
+    #[test]
+    fn test_server_metadata() {
+        let (_server, address) = start_server();
+
+        let (status, _) = request(&address, "GET", "/v2/health/live", None);
+        assert_eq!(status, 200);
+        let (status, _) = request(&address, "GET", "/v2/health/ready", None);
+        assert_eq!(status, 200);
+        let (status, _) = request(&address, "GET", "/v2/models/simple_mul_module/ready", None);
+        assert_eq!(status, 200);
+
+        let (status, metadata) = request(&address, "GET", "/v2/models/simple_mul_module", None);
+        assert_eq!(status, 200);
+        assert_eq!(metadata["name"], "simple_mul_module");
+        assert_eq!(metadata["inputs"].as_array().unwrap().len(), 2);
+        assert_eq!(metadata["inputs"][1]["name"], "input1");
+        assert_eq!(metadata["outputs"].as_array().unwrap().len(), 1);
+
+        let (status, _) = request(&address, "GET", "/v2/models/missing", None);
+        assert_eq!(status, 404);
+    }
+
+    #[test]
+    fn test_server_infer() {
+        let (_server, address) = start_server();
+
+        let (status, response) = request(
+            &address,
+            "POST",
+            "/v2/models/simple_mul_module/infer",
+            Some(json!({
+                "id": "42",
+                "inputs": [
+                    {"name": "input1", "shape": [4], "datatype": "FP32", "data": [5, 6, 7, 8]},
+                    {"name": "input0", "shape": [4], "datatype": "FP32", "data": [[1, 2], [3, 4]]},
+                ],
+            })),
+        );
+        assert_eq!(status, 200, "{}", response);
+        assert_eq!(response["id"], "42");
+        assert_eq!(
+            response["outputs"],
+            json!([{
+                "name": "output0",
+                "datatype": "FP32",
+                "shape": [4],
+                "data": [5.0, 12.0, 21.0, 32.0],
+            }])
+        );
+
+        // a missing input is rejected before invoking the function
+        let (status, response) = request(
+            &address,
+            "POST",
+            "/v2/models/simple_mul_module/versions/1/infer",
+            Some(json!({
+                "inputs": [
+                    {"name": "input0", "shape": [4], "datatype": "FP32", "data": [1, 2, 3, 4]},
+                ],
+            })),
+        );
+        assert_eq!(status, 400);
+        assert!(response["error"]
+            .as_str()
+            .unwrap()
+            .contains("expected 2 arguments"));
+
+        let (status, _) = request(
+            &address,
+            "POST",
+            "/v2/models/simple_mul_module/infer",
+            Some(json!({
+                "inputs": [
+                    {"name": "input0", "shape": [4], "datatype": "FP32", "data": [1, 2, 3]},
+                    {"name": "input1", "shape": [4], "datatype": "FP32", "data": [1, 2, 3, 4]},
+                ],
+            })),
+        );
+        assert_eq!(status, 400);
+    }
+}
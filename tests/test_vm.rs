@@ -0,0 +1,217 @@
+#[cfg(test)]
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use iree_rs::types::{
+        allocator::IreeAllocator,
+        bytespan::IreeConstByteSpan,
+        hal_buffer::IreeHalBufferView,
+        runtime::{
+            instance::{IreeRuntimeInstance, IreeRuntimeInstanceOptionsBuilder},
+            session::{IreeRuntimeSession, IreeRuntimeSessionOptionsBuilder},
+        },
+        vm_module::IreeVmModule,
+        vm_native_module::IreeVmNativeModuleBuilder,
+        vm_value::{IreeVmValue, IreeVmValueType},
+    };
+    use iree_sys::iree::runtime::api::{
+        iree_hal_element_types_t_IREE_HAL_ELEMENT_TYPE_FLOAT_32,
+        iree_hal_encoding_types_t_IREE_HAL_ENCODING_TYPE_DENSE_ROW_MAJOR,
+        iree_runtime_call_flags_t,
+    };
+
+    use crate::common::{device_local_params, Runtime};
+
+    #[derive(Default)]
+    struct CounterState {
+        calls: usize,
+    }
+
+    #[test]
+    fn test_native_module() {
+        let Runtime {
+            session,
+            device,
+            instance,
+            ..
+        } = &Runtime::local_task();
+
+        let module = IreeVmNativeModuleBuilder::<CounterState>::new("custom")
+            .function(
+                "echo",
+                &[IreeVmValueType::Ref],
+                &[IreeVmValueType::Ref],
+                |state, mut args| {
+                    state.calls += 1;
+                    Ok(vec![args.remove(0)])
+                },
+            )
+            .function(
+                "explode",
+                &[IreeVmValueType::Ref],
+                &[IreeVmValueType::Ref],
+                |_, _| panic!("boom"),
+            )
+            .build(instance)
+            .unwrap();
+        session.append_module(&module).unwrap();
+
+        let data = [1.0f32, 2.0, 3.0, 4.0];
+        let byte_span = IreeConstByteSpan::from_slice(&data);
+        let buffer_params = device_local_params();
+        let input = IreeHalBufferView::allocate_buffer_copy(
+            device,
+            &session.device_allocator(),
+            &vec![data.len()],
+            iree_hal_element_types_t_IREE_HAL_ELEMENT_TYPE_FLOAT_32,
+            iree_hal_encoding_types_t_IREE_HAL_ENCODING_TYPE_DENSE_ROW_MAJOR,
+            &buffer_params,
+            &byte_span,
+        )
+        .unwrap();
+
+        let mut call = session.get_call_by_name("custom.echo").unwrap();
+        call.inputs_push_back_buffer_view(&input).unwrap();
+        call.invoke(iree_runtime_call_flags_t::default()).unwrap();
+        let output = call.outputs_pop_front_buffer_view().unwrap();
+        assert_eq!(output.shape().unwrap(), vec![4]);
+
+        // panics must surface as errors instead of unwinding into IREE
+        let mut call = session.get_call_by_name("custom.explode").unwrap();
+        call.inputs_push_back_buffer_view(&input).unwrap();
+        assert!(call.invoke(iree_runtime_call_flags_t::default()).is_err());
+    }
+
+    #[test]
+    fn test_native_module_export_order() {
+        let Runtime {
+            session, instance, ..
+        } = &Runtime::local_task();
+
+        // declared out of order, the exports are sorted by name when the module is built
+        let module = IreeVmNativeModuleBuilder::<()>::new("ordered")
+            .function("zeta", &[], &[IreeVmValueType::I32], |_, _| {
+                Ok(vec![IreeVmValue::I32(26)])
+            })
+            .function("alpha", &[], &[IreeVmValueType::I32], |_, _| {
+                Ok(vec![IreeVmValue::I32(1)])
+            })
+            .build(instance)
+            .unwrap();
+        session.append_module(&module).unwrap();
+        for (name, expected) in [("ordered.alpha", 1), ("ordered.zeta", 26)] {
+            let mut call = session.get_call_by_name(name).unwrap();
+            call.invoke(iree_runtime_call_flags_t::default()).unwrap();
+            assert!(matches!(call.output(0).unwrap(), IreeVmValue::I32(v) if v == expected));
+        }
+
+        assert!(IreeVmNativeModuleBuilder::<()>::new("duplicated")
+            .function("f", &[], &[], |_, _| Ok(vec![]))
+            .function("f", &[], &[], |_, _| Ok(vec![]))
+            .build(instance)
+            .is_err());
+    }
+
+    #[test]
+    fn test_cconv() {
+        assert_eq!(
+            IreeVmValueType::cconv(&[IreeVmValueType::Ref, IreeVmValueType::I32], &[]),
+            "0ri_v"
+        );
+        assert!(matches!(
+            IreeVmValue::F32(1.0).value_type(),
+            IreeVmValueType::F32
+        ));
+    }
+
+    #[test]
+    fn test_bytecode_module_shared() {
+        let allocator = IreeAllocator::system_allocator();
+        let options = IreeRuntimeInstanceOptionsBuilder::default()
+            .use_all_available_drivers()
+            .build();
+        let instance = IreeRuntimeInstance::try_from_options(&options, &allocator).unwrap();
+        let device = instance.try_create_default_device("local-task").unwrap();
+
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/iree-sys/examples/simple_mul_module.vmfb"
+        );
+        let module = IreeVmModule::from_file(&instance, path).unwrap();
+        assert_eq!(module.name(), "module");
+        let data = std::fs::read(path).unwrap();
+        assert_eq!(
+            IreeVmModule::from_bytecode(&instance, &data)
+                .unwrap()
+                .name(),
+            "module"
+        );
+        assert!(IreeVmModule::from_bytecode(&instance, b"not a module").is_err());
+
+        // the module outlives the sessions it is appended to
+        let sessions = (0..4)
+            .map(|_| {
+                let session_options = IreeRuntimeSessionOptionsBuilder::default().build();
+                let session = IreeRuntimeSession::create_with_device(
+                    &instance,
+                    &session_options,
+                    &device,
+                    &allocator,
+                )
+                .unwrap();
+                session.append_module(&module).unwrap();
+                session
+            })
+            .collect::<Vec<_>>();
+        drop(module);
+        for session in sessions.iter() {
+            session.get_call_by_name("module.simple_mul").unwrap();
+        }
+    }
+
+    #[test]
+    fn test_call_outputs() {
+        let Runtime {
+            session, instance, ..
+        } = &Runtime::local_task();
+
+        let module = IreeVmNativeModuleBuilder::<()>::new("custom")
+            .function(
+                "scalars",
+                &[],
+                &[
+                    IreeVmValueType::I32,
+                    IreeVmValueType::F64,
+                    IreeVmValueType::I64,
+                ],
+                |_, _| {
+                    Ok(vec![
+                        IreeVmValue::I32(7),
+                        IreeVmValue::F64(0.5),
+                        IreeVmValue::I64(-1),
+                    ])
+                },
+            )
+            .build(instance)
+            .unwrap();
+        session.append_module(&module).unwrap();
+
+        let mut call = session.get_call_by_name("custom.scalars").unwrap();
+        call.invoke(iree_runtime_call_flags_t::default()).unwrap();
+        assert_eq!(call.outputs_len(), 3);
+        assert!(matches!(call.output(1).unwrap(), IreeVmValue::F64(v) if v == 0.5));
+        assert!(call.output(3).is_err());
+
+        let outputs = call.take_outputs().unwrap();
+        assert!(matches!(
+            outputs[..],
+            [
+                IreeVmValue::I32(7),
+                IreeVmValue::F64(_),
+                IreeVmValue::I64(-1)
+            ]
+        ));
+        assert_eq!(call.outputs_len(), 0);
+    }
+}
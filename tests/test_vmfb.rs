@@ -0,0 +1,232 @@
+#[cfg(test)]
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use iree_rs::{
+        err::IreeErrorKind,
+        types::{
+            allocator::IreeAllocator,
+            runtime::{
+                instance::{IreeRuntimeInstance, IreeRuntimeInstanceOptionsBuilder},
+                session::{IreeRuntimeSession, IreeRuntimeSessionOptionsBuilder},
+            },
+        },
+        vmfb::{
+            inspect::IreeVmfbExecutableContainer,
+            module::IreeVmfbModule,
+            target::{
+                host_cpu_features, runtime_bytecode_version, IreeVmfbCpuFeatures,
+                IreeVmfbIncompatibility,
+            },
+            variants::IreeVmfbVariants,
+        },
+    };
+
+    use crate::common::compile_mlir;
+
+    fn simple_mul_module() -> Vec<u8> {
+        std::fs::read(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/iree-sys/examples/simple_mul_module.vmfb"
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_vmfb_target_info() {
+        let data = simple_mul_module();
+        let module = IreeVmfbModule::parse(&data).unwrap();
+        assert_eq!(module.name(), "module");
+        assert_eq!(
+            module.bytecode_version().unwrap(),
+            runtime_bytecode_version()
+        );
+
+        let info = module.target_info().unwrap();
+        assert_eq!(info.executable_formats, vec!["vmvx-bytecode-fb"]);
+        // vmvx executables aren't native code, so they need no CPU features
+        assert_eq!(info.cpu_features, IreeVmfbCpuFeatures::Known(vec![]));
+        module.check_host_compatibility().unwrap();
+    }
+
+    #[test]
+    fn test_vmfb_known_metadata() {
+        // checks the FlatBuffer field indices against what the pinned compiler emits
+        let Some(data) = compile_mlir(
+            "known_metadata",
+            r#"
+module @known {
+  func.func @first(%arg0: tensor<4xf32>) -> tensor<4xf32> attributes {
+    iree.reflection = {answer = "42"}
+  } {
+    return %arg0 : tensor<4xf32>
+  }
+  func.func @second(%arg0: tensor<4xi32>) -> tensor<4xi32> {
+    return %arg0 : tensor<4xi32>
+  }
+}
+"#,
+        ) else {
+            return;
+        };
+        let module = IreeVmfbModule::parse(&data).unwrap();
+        assert_eq!(module.name(), "known");
+        assert_eq!(
+            module.bytecode_version().unwrap(),
+            runtime_bytecode_version()
+        );
+
+        let exports = module.exported_functions().unwrap();
+        let first = exports.iter().find(|f| f.name == "first").unwrap();
+        assert_eq!(first.signature.calling_convention, "0r_r");
+        assert!(first.signature.attrs.contains(&("answer", "42")));
+        assert!(exports.iter().any(|f| f.name == "second"));
+
+        let dependencies = module.dependencies().unwrap();
+        assert!(dependencies.iter().any(|d| d.name == "hal" && !d.optional));
+        let info = module.target_info().unwrap();
+        assert!(info
+            .executable_formats
+            .iter()
+            .all(|format| format.starts_with("embedded-elf-")));
+    }
+
+    #[test]
+    fn test_vmfb_device_compatibility() {
+        let data = simple_mul_module();
+        let module = IreeVmfbModule::parse(&data).unwrap();
+
+        let allocator = IreeAllocator::system_allocator();
+        let options = IreeRuntimeInstanceOptionsBuilder::default()
+            .use_all_available_drivers()
+            .build();
+        let instance = IreeRuntimeInstance::try_from_options(&options, &allocator).unwrap();
+        let device = instance.try_create_default_device("local-task").unwrap();
+        // vmvx is always available on local devices
+        module.check_device_compatibility(&device).unwrap();
+    }
+
+    #[test]
+    fn test_vmfb_invalid() {
+        let data = simple_mul_module();
+        for invalid in [&data[..100], b"not a module".as_slice(), &[]] {
+            let err = IreeVmfbModule::parse(invalid).err().unwrap();
+            assert!(matches!(err.kind(), IreeErrorKind::InvalidVmfb(_)));
+        }
+    }
+
+    #[test]
+    fn test_vmfb_variants() {
+        let data = simple_mul_module();
+        let mut variants = IreeVmfbVariants::default();
+        variants
+            .variant("generic", &data)
+            .variant_with_cpu_features("unsupported", &data, &["not-a-cpu-feature"]);
+        assert_eq!(variants.select_for_host().unwrap().label, "generic");
+
+        // a variant using more of the host's features is preferred
+        if let Some(feature) = host_cpu_features().first() {
+            variants.variant_with_cpu_features("specialized", &data, &[feature]);
+            assert_eq!(variants.select_for_host().unwrap().label, "specialized");
+        }
+
+        // variants that can't be told apart aren't guessed between
+        let mut tied = IreeVmfbVariants::default();
+        tied.variant("a", &data).variant("b", &data);
+        let err = tied.select_for_host().err().unwrap();
+        assert!(matches!(
+            err.kind(),
+            IreeErrorKind::AmbiguousVmfbVariants(labels) if labels == &["a", "b"]
+        ));
+
+        let mut unsupported = IreeVmfbVariants::default();
+        unsupported.variant_with_cpu_features("unsupported", &data, &["not-a-cpu-feature"]);
+        let err = unsupported.select_for_host().err().unwrap();
+        assert!(matches!(err.kind(), IreeErrorKind::IncompatibleVmfb(..)));
+    }
+
+    #[test]
+    fn test_vmfb_variants_unknown_cpu_features() {
+        let Some(data) = compile_mlir(
+            "simple_mul_llvm_cpu",
+            r#"
+func.func @simple_mul(%arg0: tensor<4xf32>, %arg1: tensor<4xf32>) -> tensor<4xf32> {
+  %0 = arith.mulf %arg0, %arg1 : tensor<4xf32>
+  return %0 : tensor<4xf32>
+}
+"#,
+        ) else {
+            return;
+        };
+        let module = IreeVmfbModule::parse(&data).unwrap();
+        let info = module.target_info().unwrap();
+        assert_eq!(info.cpu_features, IreeVmfbCpuFeatures::Unknown);
+
+        // native executables compiled for unknown features aren't picked unless declared
+        let mut variants = IreeVmfbVariants::default();
+        variants.variant("native", &data);
+        let err = variants.select_for_host().err().unwrap();
+        assert!(matches!(
+            err.kind(),
+            IreeErrorKind::IncompatibleVmfb(_, reasons)
+                if reasons == &[IreeVmfbIncompatibility::UnknownCpuFeatures]
+        ));
+        variants.variant_with_cpu_features("generic", &data, &[]);
+        assert_eq!(variants.select_for_host().unwrap().label, "generic");
+    }
+
+    #[test]
+    fn test_vmfb_append_variant() {
+        let data = simple_mul_module();
+        let mut variants = IreeVmfbVariants::default();
+        variants
+            .variant_with_cpu_features("unsupported", &data, &["not-a-cpu-feature"])
+            .variant("generic", &data);
+
+        let allocator = IreeAllocator::system_allocator();
+        let options = IreeRuntimeInstanceOptionsBuilder::default()
+            .use_all_available_drivers()
+            .build();
+        let instance = IreeRuntimeInstance::try_from_options(&options, &allocator).unwrap();
+        let device = instance.try_create_default_device("local-task").unwrap();
+        let session_options = IreeRuntimeSessionOptionsBuilder::default().build();
+        let session = IreeRuntimeSession::create_with_device(
+            &instance,
+            &session_options,
+            &device,
+            &allocator,
+        )
+        .unwrap();
+        let label = session.append_bytecode_module_variant(&variants).unwrap();
+        assert_eq!(label, "generic");
+        session.get_call_by_name("module.simple_mul").unwrap();
+    }
+
+    #[test]
+    fn test_vmfb_inspect() {
+        let data = simple_mul_module();
+        let module = IreeVmfbModule::parse(&data).unwrap();
+
+        let exports = module.exported_functions().unwrap();
+        let names = exports.iter().map(|f| f.name).collect::<Vec<_>>();
+        assert_eq!(names, vec!["simple_mul", "__init"]);
+        assert_eq!(exports[0].signature.calling_convention, "0rr_r");
+
+        let imports = module.imported_functions().unwrap();
+        assert!(imports.iter().all(|f| f.name.starts_with("hal.")));
+        let dependencies = module.dependencies().unwrap();
+        assert_eq!(dependencies.len(), 1);
+        assert_eq!(dependencies[0].name, "hal");
+
+        let executables = module.executables().unwrap();
+        assert_eq!(executables.len(), 1);
+        assert!(matches!(
+            executables[0].container,
+            IreeVmfbExecutableContainer::Archive(_)
+        ));
+        assert!(module.rodata_segments().unwrap()[executables[0].segment].external);
+
+        assert!(module.abi_changes(&module).unwrap().is_empty());
+    }
+}